@@ -0,0 +1,238 @@
+use std::io::{Cursor, Write};
+
+use chrono::Utc;
+use zip::{write::SimpleFileOptions, CompressionMethod, ZipWriter};
+
+use crate::data::{section::Status, strip_formatting, volume::Kind, Entry, Section, Volume};
+
+use super::{escape, sections_body, xhtml, STYLE};
+
+/// A chapter of the book.
+struct Chapter {
+    file: String,
+    title: String,
+    part: usize,
+    headings: Vec<(u32, String)>,
+}
+
+/// Package a volume as an EPUB book, with a chapter for the intro and for
+/// each entry that has text, and the images they show.
+pub fn volume(volume: &Volume) -> Vec<u8> {
+    let storage = volume.index().storage();
+    let title = volume.title();
+    let show_parts = volume.parts_count() > 1;
+    let show_timestamps = volume.kind() == Kind::Journal;
+
+    let mut files = Vec::new();
+    let mut images = Vec::new();
+
+    // Title page and intro.
+    let mut body = format!("<h1>{}</h1>\n", xhtml(title));
+    if let Some(subtitle) = volume.subtitle() {
+        body += &format!("<p class=\"subtitle\">{}</p>\n", xhtml(subtitle));
+    }
+    for line in volume.intro().lines() {
+        body += &format!("<p>{}</p>\n", xhtml(line));
+    }
+    files.push(("intro.xhtml".to_owned(), page(title, &body)));
+
+    // Entries.
+    let mut chapters = Vec::new();
+    for (part, entries) in volume.entries_by_part() {
+        for entry in entries {
+            let sections: Vec<_> = entry
+                .sections()
+                .filter(|s| s.status() != Status::Missing)
+                .collect();
+            if sections.is_empty() {
+                continue;
+            }
+
+            let chapter = Chapter {
+                file: format!("entry-{}.xhtml", 1 + chapters.len()),
+                title: entry.title().to_owned(),
+                part,
+                headings: sections
+                    .iter()
+                    .filter_map(|s| Some((s.id(), s.heading()?.clone())))
+                    .collect(),
+            };
+            let body = entry_body(&entry, &sections, show_timestamps, &mut images);
+            files.push((chapter.file.clone(), page(entry.title(), &body)));
+            chapters.push(chapter);
+        }
+    }
+
+    // Table of contents.
+    let mut nav = String::from("<li><a href=\"intro.xhtml\">Introduction</a></li>\n");
+    let mut current_part = None;
+    for chapter in &chapters {
+        if show_parts && current_part != Some(chapter.part) {
+            if current_part.is_some() {
+                nav += "</ol></li>\n";
+            }
+            let part = roman::to(1 + chapter.part as i32).unwrap();
+            nav += &format!("<li><span>Volume {part}</span><ol>\n");
+            current_part = Some(chapter.part);
+        }
+        nav += &format!(
+            "<li><a href=\"{}\">{}</a>",
+            chapter.file,
+            xhtml(&chapter.title)
+        );
+        if !chapter.headings.is_empty() {
+            nav += "<ol>";
+            for (id, heading) in &chapter.headings {
+                nav += &format!(
+                    "<li><a href=\"{}#section-{id}\">{}</a></li>",
+                    chapter.file,
+                    xhtml(heading)
+                );
+            }
+            nav += "</ol>";
+        }
+        nav += "</li>\n";
+    }
+    if current_part.is_some() {
+        nav += "</ol></li>\n";
+    }
+    let nav = format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>
+<!DOCTYPE html>
+<html xmlns=\"http://www.w3.org/1999/xhtml\" xmlns:epub=\"http://www.idpf.org/2007/ops\" xml:lang=\"en\" lang=\"en\">
+<head><title>Contents</title></head>
+<body>
+<nav epub:type=\"toc\" id=\"toc\">
+<h1>Contents</h1>
+<ol>
+{nav}</ol>
+</nav>
+</body>
+</html>
+"
+    );
+
+    // Package document.
+    let mut manifest = String::from(
+        "<item id=\"nav\" href=\"nav.xhtml\" media-type=\"application/xhtml+xml\" properties=\"nav\"/>
+<item id=\"style\" href=\"style.css\" media-type=\"text/css\"/>
+<item id=\"intro\" href=\"intro.xhtml\" media-type=\"application/xhtml+xml\"/>
+",
+    );
+    let mut spine = String::from("<itemref idref=\"intro\"/>\n");
+    for (i, chapter) in chapters.iter().enumerate() {
+        manifest += &format!(
+            "<item id=\"entry-{}\" href=\"{}\" media-type=\"application/xhtml+xml\"/>\n",
+            i + 1,
+            chapter.file
+        );
+        spine += &format!("<itemref idref=\"entry-{}\"/>\n", i + 1);
+    }
+    for (i, image) in images.iter().enumerate() {
+        manifest += &format!(
+            "<item id=\"image-{}\" href=\"images/{}.jpg\" media-type=\"image/jpeg\"/>\n",
+            i + 1,
+            escape(image)
+        );
+    }
+    let opf = format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>
+<package xmlns=\"http://www.idpf.org/2007/opf\" version=\"3.0\" unique-identifier=\"book-id\">
+<metadata xmlns:dc=\"http://purl.org/dc/elements/1.1/\">
+<dc:identifier id=\"book-id\">urn:edat:{id}</dc:identifier>
+<dc:title>{title}</dc:title>
+<dc:creator>{creator}</dc:creator>
+<dc:language>en</dc:language>
+<meta property=\"dcterms:modified\">{modified}</meta>
+</metadata>
+<manifest>
+{manifest}</manifest>
+<spine>
+{spine}</spine>
+</package>
+",
+        id = escape(volume.id()),
+        title = escape(&strip_formatting(title)),
+        creator = escape(&volume.owner().full_name()),
+        modified = Utc::now().format("%Y-%m-%dT%H:%M:%SZ"),
+    );
+
+    // The mimetype has to come first, uncompressed.
+    let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+    let stored = SimpleFileOptions::default().compression_method(CompressionMethod::Stored);
+    let options = SimpleFileOptions::default();
+    zip.start_file("mimetype", stored).unwrap();
+    zip.write_all(b"application/epub+zip").unwrap();
+    zip.start_file("META-INF/container.xml", options).unwrap();
+    zip.write_all(
+        b"<?xml version=\"1.0\" encoding=\"UTF-8\"?>
+<container version=\"1.0\" xmlns=\"urn:oasis:names:tc:opendocument:xmlns:container\">
+<rootfiles>
+<rootfile full-path=\"OEBPS/content.opf\" media-type=\"application/oebps-package+xml\"/>
+</rootfiles>
+</container>
+",
+    )
+    .unwrap();
+    zip.start_file("OEBPS/content.opf", options).unwrap();
+    zip.write_all(opf.as_bytes()).unwrap();
+    zip.start_file("OEBPS/nav.xhtml", options).unwrap();
+    zip.write_all(nav.as_bytes()).unwrap();
+    zip.start_file("OEBPS/style.css", options).unwrap();
+    zip.write_all(STYLE.as_bytes()).unwrap();
+    for (file, content) in files {
+        zip.start_file(format!("OEBPS/{file}"), options).unwrap();
+        zip.write_all(content.as_bytes()).unwrap();
+    }
+    for image in images {
+        let content = storage
+            .read(&format!("content/images/{image}.jpg"))
+            .unwrap();
+        zip.start_file(format!("OEBPS/images/{image}.jpg"), stored)
+            .unwrap();
+        zip.write_all(&content).unwrap();
+    }
+    zip.finish().unwrap().into_inner()
+}
+
+/// Render an entry's sections, adding the images they show to `images`.
+fn entry_body(
+    entry: &Entry,
+    sections: &[Section],
+    show_timestamps: bool,
+    images: &mut Vec<String>,
+) -> String {
+    let storage = entry.index().storage();
+    let mut image_src = |image: &str| {
+        if !storage.exists(&format!("content/images/{image}.jpg")) {
+            return None;
+        }
+        if !images.iter().any(|i| i == image) {
+            images.push(image.to_owned());
+        }
+        Some(format!("images/{}.jpg", escape(image)))
+    };
+    format!(
+        "<h1>{}</h1>\n{}",
+        xhtml(entry.title()),
+        sections_body(sections, show_timestamps, &mut image_src)
+    )
+}
+
+/// Wrap a chapter body in an XHTML document.
+fn page(title: &str, body: &str) -> String {
+    format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>
+<!DOCTYPE html>
+<html xmlns=\"http://www.w3.org/1999/xhtml\" xml:lang=\"en\" lang=\"en\">
+<head>
+<title>{}</title>
+<link rel=\"stylesheet\" type=\"text/css\" href=\"style.css\"/>
+</head>
+<body>
+{body}</body>
+</html>
+",
+        escape(&strip_formatting(title))
+    )
+}
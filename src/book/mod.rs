@@ -0,0 +1,150 @@
+use chrono::NaiveDate;
+use regex::{Captures, Regex};
+
+use crate::data::{section::Status, strip_formatting, Section};
+
+/// EPUB packaging of volumes for e-readers.
+pub mod epub;
+
+/// Print-ready documents of volumes and entries.
+pub mod print;
+
+/// Styles for the books, after the website's entry page.
+const STYLE: &str = "\
+body { font-family: serif; line-height: 1.5; }
+h1, h2, h3 { text-align: center; }
+.subtitle, .timestamp, .retro-timestamp, .comm-date, .caption { text-align: center; font-style: italic; }
+.note, .aside, .retro, .comm { margin: 1em 0; padding: 0 1em; border-left: 2px solid #999; }
+.note-desc { font-weight: bold; }
+.img { text-align: center; }
+.img img { max-width: 100%; }
+";
+
+/// Render the text of an entry's sections.
+///
+/// `image_src` gives the source to show an image from, or `None` if it is
+/// missing.
+fn sections_body(
+    sections: &[Section],
+    show_timestamps: bool,
+    image_src: &mut dyn FnMut(&str) -> Option<String>,
+) -> String {
+    let mut body = String::new();
+
+    for (i, section) in sections.iter().enumerate() {
+        if i > 0 {
+            body += "<hr/>\n";
+        }
+        body += &format!(
+            "<section id=\"section-{}\" data-date=\"{}\">\n",
+            section.id(),
+            long_date(&section.date())
+        );
+        if let Some(heading) = section.heading() {
+            body += &format!("<h2>{}</h2>\n", xhtml(heading));
+        }
+        if show_timestamps {
+            body += &format!(
+                "<p class=\"timestamp\">Added {}</p>\n",
+                long_date(&section.date())
+            );
+        }
+
+        // Closing tags for the blocks that are open.
+        let mut open = Vec::new();
+        for line in section.content().lines() {
+            let (command, rest) = match line.split_once(' ') {
+                Some((command, rest)) => (command, rest),
+                None => (line, ""),
+            };
+            let date = NaiveDate::parse_from_str(rest, "%Y-%m-%d").ok();
+            match command {
+                "/note" => {
+                    body += "<div class=\"note\">\n";
+                    if !rest.is_empty() {
+                        body += &format!("<p class=\"note-desc\">{}</p>\n", xhtml(rest));
+                    }
+                    open.push("</div>\n");
+                }
+                "/retro" => {
+                    body += "<div class=\"retro\">\n<h3>Retrospective</h3>\n";
+                    if let Some(date) = date {
+                        body += &format!(
+                            "<p class=\"retro-timestamp\">Added {}</p>\n",
+                            long_date(&date)
+                        );
+                    }
+                    open.push("</div>\n");
+                }
+                "/comms" => {
+                    body += "<div class=\"comm\">\n<p class=\"comm-date\">Commentary";
+                    if let Some(date) = date {
+                        body += &format!(" {}", long_date(&date));
+                    }
+                    body += "</p>\n<ol>\n";
+                    open.push("</ol>\n</div>\n");
+                }
+                "/comm" => {
+                    body += "<li>\n";
+                    open.push("</li>\n");
+                }
+                "/aside" => {
+                    body += "<div class=\"aside\">\n";
+                    open.push("</div>\n");
+                }
+                "/end" => {
+                    if let Some(close) = open.pop() {
+                        body += close;
+                    }
+                }
+                "/img" => {
+                    let (image, caption) = rest.split_once(' ').unwrap_or((rest, ""));
+                    body += "<div class=\"img\">\n";
+                    if let Some(src) = image_src(image) {
+                        body += &format!(
+                            "<img src=\"{src}\" alt=\"{}\"/>\n",
+                            escape(&strip_formatting(caption))
+                        );
+                    }
+                    body += &format!("<p class=\"caption\">{}</p>\n</div>\n", xhtml(caption));
+                }
+                _ => body += &format!("<p>{}</p>\n", xhtml(line)),
+            }
+        }
+        while let Some(close) = open.pop() {
+            body += close;
+        }
+
+        if section.status() == Status::Incomplete {
+            body += "<div class=\"aside\"><p><b>Incomplete section</b></p></div>\n";
+        }
+        body += "</section>\n";
+    }
+    body
+}
+
+/// Make journal text, which can hold inline HTML, well-formed XHTML.
+fn xhtml(text: &str) -> String {
+    // XHTML only knows the XML entities, so other ampersands are escaped.
+    let ampersand = Regex::new(r"&(#[0-9]+;|#x[0-9a-fA-F]+;|amp;|lt;|gt;|quot;|apos;)?").unwrap();
+    let text = ampersand.replace_all(text, |captures: &Captures| match captures.get(1) {
+        Some(entity) => format!("&{}", entity.as_str()),
+        None => "&amp;".to_owned(),
+    });
+
+    // Void elements have to be closed.
+    let void = Regex::new(r"<(br|hr)\s*/?>").unwrap();
+    void.replace_all(&text, "<$1/>").into_owned()
+}
+
+/// Escape plain text for XML.
+pub(crate) fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+pub(crate) fn long_date(date: &NaiveDate) -> String {
+    date.format("%B %-d, %Y").to_string()
+}
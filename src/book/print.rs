@@ -0,0 +1,137 @@
+use base64::{engine::general_purpose::STANDARD, Engine};
+
+use crate::data::{section::Status, strip_formatting, volume::Kind, Entry, Volume};
+
+use super::{escape, sections_body, xhtml, STYLE};
+
+/// Page layout for printing. Running heads use the paged media strings, which
+/// print-to-PDF tools fill in and browsers leave out.
+const PRINT_STYLE: &str = "\
+@page { size: letter; margin: 1in 0.9in; @top-left { content: string(title); } @top-right { content: string(date); } @bottom-center { content: counter(page); } }
+@page :first { @top-left { content: none; } @top-right { content: none; } @bottom-center { content: none; } }
+body { font-size: 11pt; }
+.title-page, .part { text-align: center; padding-top: 3in; break-after: page; }
+.title-page h1 { font-size: 28pt; string-set: title content(); }
+.part { break-before: page; }
+.part h1 { font-size: 22pt; }
+.byline { text-align: center; }
+.entry { break-before: page; }
+section[data-date] { string-set: date attr(data-date); }
+.img, h2, h3 { break-inside: avoid; break-after: avoid; }
+.hint { font-family: sans-serif; font-size: 10pt; text-align: center; color: #666; }
+@media print { .hint { display: none; } }
+";
+
+/// Render a volume as a single document ready to print, with a title page, a
+/// divider before each part, and a page for each entry that has text.
+pub fn volume(volume: &Volume) -> String {
+    let show_parts = volume.parts_count() > 1;
+    let show_timestamps = volume.kind() == Kind::Journal;
+
+    let mut body = title_page(
+        volume.title(),
+        volume.subtitle().map(String::as_str),
+        &volume.owner().full_name(),
+    );
+    if !volume.intro().is_empty() {
+        body += "<div class=\"intro\">\n";
+        for line in volume.intro().lines() {
+            body += &format!("<p>{}</p>\n", xhtml(line));
+        }
+        body += "</div>\n";
+    }
+
+    for (part, entries) in volume.entries_by_part() {
+        let entries: Vec<_> = entries.into_iter().filter(has_text).collect();
+        if entries.is_empty() {
+            continue;
+        }
+        if show_parts {
+            body += &format!(
+                "<div class=\"part\"><h1>Volume {}</h1></div>\n",
+                roman::to(1 + part as i32).unwrap()
+            );
+        }
+        for entry in entries {
+            body += &format!(
+                "<article class=\"entry\">\n<h1>{}</h1>\n{}</article>\n",
+                xhtml(entry.title()),
+                entry_sections(&entry, show_timestamps)
+            );
+        }
+    }
+
+    document(volume.title(), &body)
+}
+
+/// Render an entry as a single document ready to print, with a title page
+/// naming the volume it belongs to.
+pub fn entry(entry: &Entry) -> String {
+    let volume = entry.parent_volume();
+    let mut from = format!("From {}", volume.title());
+    if volume.parts_count() > 1 {
+        from += &format!(
+            ", volume {}",
+            roman::to(1 + entry.parent_volume_part() as i32).unwrap()
+        );
+    }
+
+    let mut body = title_page(entry.title(), Some(&from), &entry.author().full_name());
+    body += &format!(
+        "<article>\n{}</article>\n",
+        entry_sections(entry, volume.kind() == Kind::Journal)
+    );
+
+    document(entry.title(), &body)
+}
+
+/// Whether an entry has any sections to print.
+fn has_text(entry: &Entry) -> bool {
+    entry.sections().any(|s| s.status() != Status::Missing)
+}
+
+/// Render the sections of an entry that have text, with their images inlined.
+fn entry_sections(entry: &Entry, show_timestamps: bool) -> String {
+    let storage = entry.index().storage();
+    let sections: Vec<_> = entry
+        .sections()
+        .filter(|s| s.status() != Status::Missing)
+        .collect();
+    let mut image_src = |image: &str| {
+        let content = storage.read(&format!("content/images/{image}.jpg")).ok()?;
+        Some(format!(
+            "data:image/jpeg;base64,{}",
+            STANDARD.encode(content)
+        ))
+    };
+    sections_body(&sections, show_timestamps, &mut image_src)
+}
+
+fn title_page(title: &str, subtitle: Option<&str>, author: &str) -> String {
+    let mut page = format!("<div class=\"title-page\">\n<h1>{}</h1>\n", xhtml(title));
+    if let Some(subtitle) = subtitle {
+        page += &format!("<p class=\"subtitle\">{}</p>\n", xhtml(subtitle));
+    }
+    page += &format!("<p class=\"byline\">{}</p>\n</div>\n", escape(author));
+    page
+}
+
+/// Wrap a book body in a standalone HTML document.
+fn document(title: &str, body: &str) -> String {
+    format!(
+        "<!DOCTYPE html>
+<html lang=\"en\">
+<head>
+<meta charset=\"utf-8\"/>
+<title>{}</title>
+<style>
+{STYLE}{PRINT_STYLE}</style>
+</head>
+<body>
+<p class=\"hint\">Print this page or save it as a PDF to make a copy of the book.</p>
+{body}</body>
+</html>
+",
+        escape(&strip_formatting(title))
+    )
+}
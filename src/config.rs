@@ -0,0 +1,219 @@
+use std::{
+    env, fs,
+    net::{IpAddr, Ipv4Addr, SocketAddr},
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use chrono::Utc;
+use serde::Deserialize;
+
+use crate::{data::snapshot::Retention, digest::Smtp};
+
+/// The config file read when `EDAT_CONFIG` is not set.
+const DEFAULT_CONFIG_PATH: &str = "edat.json";
+
+/// Server settings for one instance of the website.
+///
+/// Settings are read from a JSON config file, then overridden by environment
+/// variables. Relative paths are resolved against the data root.
+#[derive(Deserialize)]
+#[serde(default)]
+pub struct Config {
+    /// The directory containing `content`, `users` and `archived`.
+    ///
+    /// Override with `EDAT_DATA_ROOT`.
+    pub data_root: PathBuf,
+
+    /// A database file to keep the journal data in instead of the data root.
+    ///
+    /// Override with `EDAT_DATABASE`.
+    pub database: Option<PathBuf>,
+
+    /// The address to listen on.
+    ///
+    /// Override with `EDAT_ADDRESS`.
+    pub address: IpAddr,
+
+    /// The port to listen on.
+    ///
+    /// Override with `EDAT_PORT`.
+    pub port: u16,
+
+    /// The address the website is reached at, like `https://example.com`,
+    /// for links that leave the website. Worked out from each request if
+    /// unset.
+    ///
+    /// Override with `EDAT_SITE_URL`.
+    pub site_url: Option<String>,
+
+    /// Where archive zips of the journal data are staged.
+    ///
+    /// Override with `EDAT_ARCHIVE_DIR`.
+    pub archive_dir: PathBuf,
+
+    /// Where the generated link preview image is written.
+    pub preview_image: PathBuf,
+
+    /// Whether to enable the music pages, which need Spotify credentials.
+    ///
+    /// Override with `EDAT_SPOTIFY`.
+    pub spotify: bool,
+
+    /// The file holding the Spotify client id.
+    pub spotify_client_id: PathBuf,
+
+    /// The file holding the Spotify client secret.
+    pub spotify_client_secret: PathBuf,
+
+    /// How many days removed resources stay in the trash before they are
+    /// purged at startup. Nothing is purged automatically if unset.
+    ///
+    /// Override with `EDAT_TRASH_RETENTION_DAYS`.
+    pub trash_retention_days: Option<u64>,
+
+    /// Where snapshots of the journal data are kept.
+    ///
+    /// Override with `EDAT_SNAPSHOT_DIR`.
+    pub snapshot_dir: PathBuf,
+
+    /// How many hours apart snapshots are taken while the server runs. None
+    /// are taken on a schedule if unset.
+    ///
+    /// Override with `EDAT_SNAPSHOT_INTERVAL_HOURS`.
+    pub snapshot_interval_hours: Option<u64>,
+
+    /// How many daily, weekly and monthly snapshots to keep.
+    ///
+    /// Override with `EDAT_SNAPSHOT_KEEP_DAILY`, `EDAT_SNAPSHOT_KEEP_WEEKLY`
+    /// and `EDAT_SNAPSHOT_KEEP_MONTHLY`.
+    pub snapshot_retention: Retention,
+
+    /// The mail server to send email digests through. No digests are sent if
+    /// unset, or if `site_url` is unset, since the links need it.
+    ///
+    /// Override with `EDAT_SMTP_HOST`, `EDAT_SMTP_PORT`, `EDAT_SMTP_STARTTLS`,
+    /// `EDAT_SMTP_USERNAME`, `EDAT_SMTP_PASSWORD` and `EDAT_SMTP_FROM`.
+    pub smtp: Option<Smtp>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            data_root: PathBuf::from("."),
+            database: None,
+            address: IpAddr::V4(Ipv4Addr::UNSPECIFIED),
+            port: if cfg!(debug_assertions) { 3000 } else { 80 },
+            site_url: None,
+            archive_dir: PathBuf::from("."),
+            preview_image: PathBuf::from("content/preview.png"),
+            spotify: true,
+            spotify_client_id: PathBuf::from(".spotify_client_id"),
+            spotify_client_secret: PathBuf::from(".spotify_client_secret"),
+            trash_retention_days: None,
+            snapshot_dir: PathBuf::from("snapshots"),
+            snapshot_interval_hours: None,
+            snapshot_retention: Retention::default(),
+            smtp: None,
+        }
+    }
+}
+
+impl Config {
+    /// Read the config file named by `EDAT_CONFIG`, or `edat.json` if it
+    /// exists, and apply environment overrides.
+    pub fn load() -> Self {
+        let mut config = match env::var("EDAT_CONFIG") {
+            Ok(path) => Self::from_file(Path::new(&path)),
+            Err(_) if Path::new(DEFAULT_CONFIG_PATH).exists() => {
+                Self::from_file(Path::new(DEFAULT_CONFIG_PATH))
+            }
+            Err(_) => Config::default(),
+        };
+
+        override_with(&mut config.data_root, "EDAT_DATA_ROOT");
+        if let Ok(database) = env::var("EDAT_DATABASE") {
+            config.database = Some(database.into());
+        }
+        override_with(&mut config.address, "EDAT_ADDRESS");
+        override_with(&mut config.port, "EDAT_PORT");
+        if let Ok(site_url) = env::var("EDAT_SITE_URL") {
+            config.site_url = Some(site_url);
+        }
+        override_with(&mut config.archive_dir, "EDAT_ARCHIVE_DIR");
+        override_with(&mut config.spotify, "EDAT_SPOTIFY");
+        if env::var("EDAT_TRASH_RETENTION_DAYS").is_ok() {
+            let mut days = 0;
+            override_with(&mut days, "EDAT_TRASH_RETENTION_DAYS");
+            config.trash_retention_days = Some(days);
+        }
+        override_with(&mut config.snapshot_dir, "EDAT_SNAPSHOT_DIR");
+        if env::var("EDAT_SNAPSHOT_INTERVAL_HOURS").is_ok() {
+            let mut hours = 0;
+            override_with(&mut hours, "EDAT_SNAPSHOT_INTERVAL_HOURS");
+            config.snapshot_interval_hours = Some(hours);
+        }
+        let retention = &mut config.snapshot_retention;
+        override_with(&mut retention.daily, "EDAT_SNAPSHOT_KEEP_DAILY");
+        override_with(&mut retention.weekly, "EDAT_SNAPSHOT_KEEP_WEEKLY");
+        override_with(&mut retention.monthly, "EDAT_SNAPSHOT_KEEP_MONTHLY");
+        if let Ok(host) = env::var("EDAT_SMTP_HOST") {
+            config.smtp.get_or_insert_with(Smtp::default).host = host;
+        }
+        if let Some(smtp) = &mut config.smtp {
+            override_with(&mut smtp.port, "EDAT_SMTP_PORT");
+            override_with(&mut smtp.starttls, "EDAT_SMTP_STARTTLS");
+            if let Ok(username) = env::var("EDAT_SMTP_USERNAME") {
+                smtp.username = Some(username);
+            }
+            if let Ok(password) = env::var("EDAT_SMTP_PASSWORD") {
+                smtp.password = Some(password);
+            }
+            override_with(&mut smtp.from, "EDAT_SMTP_FROM");
+        }
+
+        // Resolve paths against the data root.
+        let root = config.data_root.clone();
+        for path in [
+            &mut config.archive_dir,
+            &mut config.snapshot_dir,
+            &mut config.preview_image,
+            &mut config.spotify_client_id,
+            &mut config.spotify_client_secret,
+        ] {
+            *path = root.join(&*path);
+        }
+        if let Some(database) = &mut config.database {
+            *database = root.join(&*database);
+        }
+
+        config
+    }
+
+    fn from_file(path: &Path) -> Self {
+        let config = fs::read_to_string(path)
+            .unwrap_or_else(|e| panic!("error reading config file {}: {e}", path.display()));
+        serde_json::from_str(&config)
+            .unwrap_or_else(|e| panic!("config file {} invalid json: {e}", path.display()))
+    }
+
+    /// The removal time before which trashed resources are purged, if any.
+    pub fn trash_cutoff(&self) -> Option<i64> {
+        self.trash_retention_days
+            .map(|days| Utc::now().timestamp() - days as i64 * 24 * 60 * 60)
+    }
+
+    /// The socket address to listen on.
+    pub fn socket_address(&self) -> SocketAddr {
+        SocketAddr::new(self.address, self.port)
+    }
+}
+
+/// Replace a setting with the value of an environment variable, if it is set.
+fn override_with<T: FromStr>(setting: &mut T, var: &str) {
+    if let Ok(value) = env::var(var) {
+        *setting = value
+            .parse()
+            .unwrap_or_else(|_| panic!("invalid value for {var}: {value}"));
+    }
+}
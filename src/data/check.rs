@@ -0,0 +1,477 @@
+use std::{collections::HashSet, fmt::Display, sync::Arc};
+
+use indexmap::IndexMap;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use crate::search;
+
+use super::{
+    storage::{Storage, Transaction},
+    *,
+};
+
+/// The part of the index file the check needs.
+#[derive(Deserialize)]
+struct IndexVolumes {
+    volumes: Vec<String>,
+}
+
+/// A problem found in the journal data.
+pub struct Problem {
+    /// What is wrong.
+    pub description: String,
+
+    /// Whether the problem was repaired.
+    pub fixed: bool,
+}
+
+impl Display for Problem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.description)?;
+        if self.fixed {
+            write!(f, " (fixed)")?;
+        }
+        Ok(())
+    }
+}
+
+/// The state of a check in progress.
+struct Checker<'a> {
+    storage: &'a dyn Storage,
+    fix: bool,
+    problems: Vec<Problem>,
+    transaction: Transaction,
+    loadable: bool,
+    stale_volumes: Vec<String>,
+    stale_entries: Vec<String>,
+    stale_sections: Vec<u32>,
+}
+
+impl Checker<'_> {
+    fn report(&mut self, description: String) {
+        self.problems.push(Problem {
+            description,
+            fixed: false,
+        });
+    }
+
+    /// Report a problem, which is repaired when fixing.
+    fn report_fixable(&mut self, description: String) {
+        self.problems.push(Problem {
+            description,
+            fixed: self.fix,
+        });
+    }
+
+    /// Read and parse a data file, reporting it if that fails.
+    fn read<T: DeserializeOwned>(&mut self, path: &str) -> Option<T> {
+        let data = match self.storage.read(path) {
+            Ok(data) => data,
+            Err(_) => {
+                self.report(format!("{path} is missing"));
+                return None;
+            }
+        };
+        match serde_json::from_slice(&data) {
+            Ok(data) => Some(data),
+            Err(e) => {
+                self.report(format!("{path} is invalid: {e}"));
+                None
+            }
+        }
+    }
+
+    /// Read and parse a data file the index can't load without.
+    fn read_required<T: DeserializeOwned>(&mut self, path: &str) -> Option<T> {
+        let data = self.read(path);
+        if data.is_none() {
+            self.loadable = false;
+        }
+        data
+    }
+
+    /// Queue a repaired data file to be written.
+    fn write<T: Serialize>(&mut self, path: String, data: &T) {
+        self.transaction
+            .write(path, serde_json::to_vec_pretty(data).unwrap());
+    }
+
+    /// Whether a search index file is missing, reporting it if so.
+    fn missing_search_index(&mut self, path: String) -> bool {
+        if self.storage.exists(&path) {
+            return false;
+        }
+        self.report_fixable(format!("{path} is missing"));
+        if self.fix {
+            // A placeholder lets the index load so the real one can be built.
+            self.transaction
+                .write(path, search::Index::new().to_bytes());
+        }
+        true
+    }
+}
+
+/// Check the journal data for inconsistencies, without loading it into an
+/// [`Index`].
+///
+/// When `fix` is set, problems that can be repaired without losing anything
+/// are repaired. Every problem found is returned.
+pub fn check(storage: Arc<dyn Storage>, fix: bool) -> Vec<Problem> {
+    let mut checker = Checker {
+        storage: storage.as_ref(),
+        fix,
+        problems: Vec::new(),
+        transaction: Transaction::new(),
+        loadable: true,
+        stale_volumes: Vec::new(),
+        stale_entries: Vec::new(),
+        stale_sections: Vec::new(),
+    };
+
+    // The data structures below are only known at the current schema.
+    match migration::schema_version(checker.storage) {
+        Ok(version) if version < migration::SCHEMA_VERSION => checker.report(format!(
+            "data is at schema version {version} and needs to be migrated to {}",
+            migration::SCHEMA_VERSION
+        )),
+        Ok(version) if version > migration::SCHEMA_VERSION => checker.report(format!(
+            "data is at schema version {version}, newer than {}",
+            migration::SCHEMA_VERSION
+        )),
+        _ => {}
+    }
+
+    // Users.
+    let user_ids: Vec<String> = checker
+        .read_required("users/users.json")
+        .unwrap_or_default();
+    let users: HashSet<_> = user_ids
+        .into_iter()
+        .filter(|u| {
+            checker
+                .read_required::<UserData>(&format!("users/{u}.json"))
+                .is_some()
+        })
+        .collect();
+
+    // Volumes and the entries they list.
+    let volume_ids = checker
+        .read_required::<IndexVolumes>("content/index.json")
+        .map(|i| i.volumes)
+        .unwrap_or_default();
+    let mut listed_entries = IndexMap::new();
+    for volume_id in &volume_ids {
+        let path = format!("content/volumes/{volume_id}.json");
+        let Some(volume) = checker.read_required::<VolumeData>(&path) else {
+            continue;
+        };
+        if !checker
+            .storage
+            .exists(&format!("content/volumes/{volume_id}.intro"))
+        {
+            checker.report(format!("content/volumes/{volume_id}.intro is missing"));
+        }
+        if checker.missing_search_index(format!("content/volumes/{volume_id}.index")) {
+            checker.stale_volumes.push(volume_id.clone());
+        }
+        for entry_id in volume.entries {
+            listed_entries.insert(entry_id, volume_id.clone());
+        }
+    }
+
+    // Entries and the sections they list.
+    let mut listed_sections = IndexMap::new();
+    for (entry_id, volume_id) in &listed_entries {
+        let path = format!("content/entries/{entry_id}.json");
+        let Some(mut entry) = checker.read_required::<EntryData>(&path) else {
+            continue;
+        };
+        if &entry.parent_volume.0 != volume_id {
+            checker.report_fixable(format!(
+                "entry {entry_id} is listed by volume {volume_id} but names {} as its parent",
+                entry.parent_volume.0
+            ));
+            entry.parent_volume.0 = volume_id.clone();
+            checker.write(path, &entry);
+        }
+        if !users.contains(&entry.author) {
+            checker.report(format!(
+                "entry {entry_id} has unknown author {}",
+                entry.author
+            ));
+        }
+        if checker.missing_search_index(format!("content/entries/{entry_id}.index")) {
+            checker.stale_entries.push(entry_id.clone());
+        }
+        for section_id in entry.sections {
+            listed_sections.insert(section_id, entry_id.clone());
+        }
+    }
+
+    // Entries that no volume lists.
+    for path in storage.list("content/entries").unwrap_or_default() {
+        let Some(entry_id) = path
+            .strip_prefix("content/entries/")
+            .and_then(|p| p.strip_suffix(".json"))
+        else {
+            continue;
+        };
+        if listed_entries.contains_key(entry_id) {
+            continue;
+        }
+        if let Some(entry) = checker.read::<EntryData>(&path) {
+            checker.report(format!(
+                "entry {entry_id} names {} as its parent but is missing from it",
+                entry.parent_volume.0
+            ));
+        }
+    }
+
+    // Sections.
+    let images: HashSet<_> = storage
+        .list("content/images")
+        .unwrap_or_default()
+        .into_iter()
+        .collect();
+    for (&section_id, entry_id) in &listed_sections {
+        check_section(
+            &mut checker,
+            section_id,
+            entry_id,
+            &listed_sections,
+            &users,
+            &images,
+        );
+    }
+
+    // Sections that no entry lists.
+    for path in storage.list("content/sections").unwrap_or_default() {
+        let Some(section_id) = path
+            .strip_prefix("content/sections/")
+            .and_then(|p| p.strip_suffix(".json"))
+            .and_then(|p| p.parse::<u32>().ok())
+        else {
+            continue;
+        };
+        if listed_sections.contains_key(&section_id) {
+            continue;
+        }
+        if let Some(section) = checker.read::<SectionData>(&path) {
+            checker.report(format!(
+                "section {section_id} names {} as its parent entry but is not listed by it",
+                section.parent_entry
+            ));
+        }
+    }
+
+    let Checker {
+        mut problems,
+        transaction,
+        loadable,
+        stale_volumes,
+        stale_entries,
+        stale_sections,
+        ..
+    } = checker;
+    if !fix {
+        return problems;
+    }
+
+    storage
+        .commit(transaction)
+        .expect("error writing repaired files");
+
+    // Rebuild the missing search indices once the data loads.
+    if stale_volumes.is_empty() && stale_entries.is_empty() && stale_sections.is_empty() {
+        return problems;
+    }
+    if !loadable {
+        problems.push(Problem {
+            description: "search indices can't be rebuilt until the data loads".to_owned(),
+            fixed: false,
+        });
+    } else {
+        let mut index = Index::init(storage);
+        for id in stale_volumes {
+            index.volume_mut(id).unwrap().reindex();
+        }
+        for id in stale_entries {
+            index.entry_mut(id).unwrap().reindex();
+        }
+        for id in stale_sections {
+            index.section_mut(id).unwrap().reindex();
+        }
+    }
+
+    problems
+}
+
+fn check_section(
+    checker: &mut Checker,
+    id: u32,
+    entry_id: &str,
+    sections: &IndexMap<u32, String>,
+    users: &HashSet<String>,
+    images: &HashSet<String>,
+) {
+    let path = format!("content/sections/{id}.json");
+    let Some(mut section) = checker.read_required::<SectionData>(&path) else {
+        return;
+    };
+    let mut changed = false;
+
+    if section.parent_entry != entry_id {
+        checker.report_fixable(format!(
+            "section {id} is listed by entry {entry_id} but names {} as its parent",
+            section.parent_entry
+        ));
+        section.parent_entry = entry_id.to_owned();
+        changed = true;
+    }
+
+    // Revision history.
+    let revisions: Vec<RevisionData> = checker
+        .storage
+        .read(&revision::path(id))
+        .ok()
+        .and_then(|r| serde_json::from_slice(&r).ok())
+        .unwrap_or_default();
+    for (number, revision) in revisions.iter().enumerate() {
+        if !users.contains(&revision.author) {
+            checker.report(format!(
+                "section {id} has revision {number} by unknown user {}",
+                revision.author
+            ));
+        }
+    }
+
+    // Text content, which can be recovered from the revision history.
+    let content_path = format!("content/sections/{id}.txt");
+    let content = match checker.storage.read_to_string(&content_path) {
+        Ok(content) => Some(content),
+        Err(_) => {
+            if revisions.is_empty() {
+                checker.report(format!("{content_path} is missing"));
+                None
+            } else {
+                checker.report_fixable(format!(
+                    "{content_path} is missing, but can be restored from its latest revision"
+                ));
+                let content = revision::content_at(&revisions, revisions.len() - 1);
+                checker.transaction.write(content_path, content.clone());
+                Some(content)
+            }
+        }
+    };
+
+    if checker.missing_search_index(format!("content/sections/{id}.index")) {
+        checker.stale_sections.push(id);
+    }
+
+    if let Some(content) = &content {
+        let lines = content.lines().count();
+        if lines != section.lines {
+            checker.report_fixable(format!(
+                "section {id} records {} lines but has {lines}",
+                section.lines
+            ));
+            section.lines = lines;
+            changed = true;
+        }
+        check_markup(checker, id, content, images);
+    }
+
+    // Comments.
+    for comment in &mut section.comments {
+        if !users.contains(&comment.author) {
+            checker.report(format!(
+                "section {id} has a comment by unknown user {}",
+                comment.author
+            ));
+        }
+        if section.lines == 0 {
+            // There is no line to move the comment to.
+            checker.report(format!(
+                "section {id} has a comment on line {} but no lines",
+                comment.line
+            ));
+        } else if comment.line >= section.lines {
+            checker.report_fixable(format!(
+                "section {id} has a comment on line {} past its last line, {}",
+                comment.line,
+                section.lines - 1
+            ));
+            comment.line = section.lines - 1;
+            changed = true;
+        }
+    }
+
+    // Reactions.
+    let comments: HashSet<_> = section.comments.iter().map(|c| c.uuid).collect();
+    let lines = section.lines;
+    let count = section.reactions.len();
+    section.reactions.retain(|r| {
+        let problem = if !users.contains(&r.user) {
+            format!("section {id} has a reaction by unknown user {}", r.user)
+        } else {
+            match r.target {
+                Target::Line(line) if line >= lines => {
+                    format!("section {id} has a reaction on line {line} past its last line")
+                }
+                Target::Comment(uuid) if !comments.contains(&uuid) => {
+                    format!("section {id} has a reaction to missing comment {uuid}")
+                }
+                _ => return true,
+            }
+        };
+        checker.report_fixable(problem);
+        false
+    });
+    changed |= section.reactions.len() != count;
+
+    // Perspectives.
+    for perspective in section.perspectives.clone() {
+        if !sections.contains_key(&perspective) {
+            checker.report_fixable(format!(
+                "section {id} links to missing section {perspective} as a perspective"
+            ));
+            section.perspectives.retain(|&p| p != perspective);
+            changed = true;
+        }
+    }
+
+    if changed {
+        checker.write(path, &section);
+    }
+}
+
+/// Check the block and image markup in a section's text.
+fn check_markup(checker: &mut Checker, id: u32, content: &str, images: &HashSet<String>) {
+    let mut open_blocks = Vec::new();
+    for (line_number, line) in content.lines().enumerate() {
+        if ["/note", "/retro", "/comm", "/aside"]
+            .iter()
+            .any(|b| line.starts_with(b))
+        {
+            open_blocks.push(line_number);
+        } else if line.starts_with("/end") {
+            if open_blocks.pop().is_none() {
+                checker.report(format!(
+                    "section {id} line {line_number} ends a block that was never opened"
+                ));
+            }
+        } else if line.starts_with("/img") {
+            let image = line.split(' ').nth(1).unwrap_or("");
+            if !images.contains(&format!("content/images/{image}.jpg")) {
+                checker.report(format!(
+                    "section {id} line {line_number} shows missing image {image}"
+                ));
+            }
+        }
+    }
+    for line_number in open_blocks {
+        checker.report(format!(
+            "section {id} line {line_number} opens a block that is never ended"
+        ));
+    }
+}
@@ -1,3 +1,4 @@
+use regex::Regex;
 use serde::{Deserialize, Serialize};
 
 use super::*;
@@ -10,6 +11,10 @@ pub(super) struct CommentData {
     pub(super) line: usize,
     pub(super) author: String,
     pub(super) timestamp: i64,
+    #[serde(default)]
+    pub(super) reply_to: Option<u128>,
+    #[serde(default)]
+    pub(super) mentioned: Vec<String>,
 }
 
 /// A comment thread.
@@ -40,4 +45,51 @@ pub struct Comment<'index> {
 
     /// When the comment was originally written. Edits do not change this.
     pub timestamp: i64,
+
+    /// The UUID of the comment in the same thread that this one replies to,
+    /// if any.
+    pub reply_to: Option<u128>,
+}
+
+impl<'index> Thread<'index> {
+    /// Get a comment in the thread by its UUID.
+    pub fn comment(&self, uuid: u128) -> Option<&Comment<'index>> {
+        self.comments.iter().find(|c| c.uuid == uuid)
+    }
+
+    /// The comments that don't reply to another comment in the thread, oldest
+    /// first.
+    pub fn roots(&self) -> impl Iterator<Item = &Comment<'index>> {
+        self.comments
+            .iter()
+            .filter(|c| c.reply_to.is_none_or(|uuid| self.comment(uuid).is_none()))
+    }
+
+    /// The replies to a comment, oldest first.
+    pub fn replies(&self, uuid: u128) -> impl Iterator<Item = &Comment<'index>> {
+        self.comments
+            .iter()
+            .filter(move |c| c.reply_to == Some(uuid))
+    }
+}
+
+/// The ids of the users mentioned with `@name` in a comment, each once.
+///
+/// See [`Index::user_by_mention`] for the names that are recognized.
+pub fn mentions(index: &Index, text: &str) -> Vec<String> {
+    let mut users: Vec<_> = mention_regex()
+        .captures_iter(text)
+        .filter_map(|c| index.user_by_mention(&c[2]))
+        .map(|u| u.id().to_owned())
+        .collect();
+    users.sort();
+    users.dedup();
+    users
+}
+
+/// Matches `@name` where it doesn't continue a word, like in an email
+/// address. The character before it, if any, is the first group and the name
+/// is the second.
+pub fn mention_regex() -> Regex {
+    Regex::new(r"(^|[^\w@])@(\w+)").unwrap()
 }
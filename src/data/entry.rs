@@ -1,11 +1,8 @@
-use std::fs::{self, File};
-
-use chrono::Utc;
 use serde::{Deserialize, Serialize};
 
 use crate::search;
 
-use super::*;
+use super::{storage::Transaction, *};
 
 #[derive(Serialize, Deserialize)]
 pub(super) struct EntryData {
@@ -16,6 +13,8 @@ pub(super) struct EntryData {
     pub(super) author: String,
     pub(super) parent_volume: (String, usize),
     pub(super) sections: Vec<u32>,
+    #[serde(default)]
+    pub(super) tags: Vec<String>,
 
     #[serde(skip)]
     pub(super) search_index: search::Index,
@@ -53,6 +52,16 @@ macro_rules! immut_fns {
             &self.data().summary
         }
 
+        /// The entry's tags, which group it with content on the same subject.
+        pub fn tags(&self) -> &[String] {
+            &self.data().tags
+        }
+
+        /// Whether the entry has the given tag.
+        pub fn has_tag(&self, tag: &str) -> bool {
+            self.data().tags.iter().any(|t| t == tag)
+        }
+
         /// The id of the author.
         pub fn author_id(&self) -> &str {
             &self.data().author
@@ -160,9 +169,15 @@ pub struct EntryMut<'index> {
 
 impl EntryMut<'_> {
     pub(super) fn data_mut(&mut self) -> &mut EntryData {
+        self.index.dirty_entries.data.insert(self.id.clone());
         self.index.entries.get_mut(&self.id).unwrap()
     }
 
+    /// Rebuild the search index when the wrapper is dropped.
+    pub fn reindex(&mut self) {
+        self.index.dirty_entries.search.insert(self.id.clone());
+    }
+
     pub fn as_immut(&self) -> Entry {
         Entry {
             index: &self.index,
@@ -220,14 +235,17 @@ impl EntryMut<'_> {
             }
 
             // Rename associated files.
-            let _ = fs::rename(
-                format!("content/entries/{}.json", &self.id),
-                format!("content/entries/{}.json", &new_id),
-            );
-            let _ = fs::rename(
-                format!("content/entries/{}.index", &self.id),
-                format!("content/entries/{}.index", &new_id),
-            );
+            let mut transaction = Transaction::new();
+            transaction
+                .rename(
+                    format!("content/entries/{}.json", &self.id),
+                    format!("content/entries/{}.json", &new_id),
+                )
+                .rename(
+                    format!("content/entries/{}.index", &self.id),
+                    format!("content/entries/{}.index", &new_id),
+                );
+            self.index.storage.commit(transaction).expect("error renaming entry files");
 
             // Update index registry.
             let data = self.index.entries.remove(&self.id).unwrap();
@@ -236,17 +254,25 @@ impl EntryMut<'_> {
         }
 
         self.data_mut().title = title;
+        self.reindex();
         Ok(())
     }
 
     /// Set the entry description (brief explanation).
     pub fn set_description(&mut self, description: &str) {
         self.data_mut().description = process_text(description);
+        self.reindex();
     }
 
     /// Set the entry summary (longer explanation).
     pub fn set_summary(&mut self, summary: &str) {
         self.data_mut().summary = process_text(summary);
+        self.reindex();
+    }
+
+    /// Set the entry's tags.
+    pub fn set_tags(&mut self, tags: &[String]) {
+        self.data_mut().tags = process_tags(tags);
     }
 
     /// Get the parent volume for mutation.
@@ -291,13 +317,14 @@ impl EntryMut<'_> {
         Ok(())
     }
 
-    /// Remove the entry from the journal.
+    /// Remove the entry and its sections from the journal, moving them to the
+    /// trash.
     pub fn remove(mut self) {
-        // Orphan this entry's sections.
-        let section_ids = self.section_ids().to_owned();
-        for id in section_ids {
-            self.index.section_mut(id).unwrap().remove();
-        }
+        let location = Location::Entry {
+            volume: self.parent_volume_id().to_owned(),
+            part: self.parent_volume_part(),
+            index: self.index_in_parent(),
+        };
 
         // Update parent volume.
         {
@@ -313,18 +340,37 @@ impl EntryMut<'_> {
             parent_volume.data_mut().volume_count = new_volume_count;
         }
 
+        // Archive files.
+        let storage = self.index.storage.clone();
+        let mut trash_list = trash::load(self.index);
+        let mut trash = TrashData::new(self.id.clone(), location);
+        let mut transaction = Transaction::new();
+        self.archive(&mut trash, &mut transaction);
+        trash_list.push(trash);
+        trash::stage(&trash_list, &mut transaction);
+        storage.commit(transaction).expect("error archiving entry files");
+    }
+
+    /// Take the entry and its sections out of the index and queue their files
+    /// to be archived, leaving the parent volume as it is.
+    pub(super) fn archive(mut self, trash: &mut TrashData, transaction: &mut Transaction) {
+        let section_ids = self.section_ids().to_owned();
+        for id in section_ids {
+            self.index.section_mut(id).unwrap().archive(trash, transaction);
+        }
+
         // Update index registry.
         self.index.entries.remove(&self.id);
 
-        // Archive files.
-        let now = Utc::now().timestamp();
-        let _ = fs::rename(
+        trash.archive(
+            transaction,
             format!("content/entries/{}.json", &self.id),
-            format!("archived/entry-{}-{now}", &self.id),
+            &format!("entry-{}", &self.id),
         );
-        fs::remove_file(format!("content/entries/{}.index", &self.id));
+        transaction.remove(format!("content/entries/{}.index", &self.id));
 
         // Prevent saving on drop.
+        self.index.dirty_entries.clear(&self.id);
         self.exists = false;
     }
 }
@@ -334,25 +380,37 @@ impl Drop for EntryMut<'_> {
         if !self.exists {
             return;
         }
-
-        // Create search index.
         let id = self.id.clone();
-        let title = self.title().to_owned();
-        let description = self.description().to_owned();
-        let summary = self.summary().to_owned();
-        let search_index = &mut self.data_mut().search_index;
-        *search_index = search::Index::new();
-        search_index.add_section("TITLE".to_owned(), &title);
-        search_index.add_section("DESCRIPTION".to_owned(), &description);
-        search_index.add_section("SUMMARY".to_owned(), &summary);
-        fs::write(
-            format!("content/entries/{id}.index"),
-            search_index.to_bytes(),
-        )
-        .unwrap();
-
-        // Write data.
-        let entry = File::create(format!("content/entries/{id}.json")).unwrap();
-        serde_json::to_writer_pretty(entry, self.data()).unwrap();
+        let mut transaction = Transaction::new();
+
+        // Rebuild the search index only if the text it covers changed.
+        if self.index.dirty_entries.search.remove(&id) {
+            let title = self.title().to_owned();
+            let description = self.description().to_owned();
+            let summary = self.summary().to_owned();
+            let search_index = &mut self.index.entries.get_mut(&id).unwrap().search_index;
+            *search_index = search::Index::new();
+            search_index.add_section("TITLE".to_owned(), &title);
+            search_index.add_section("DESCRIPTION".to_owned(), &description);
+            search_index.add_section("SUMMARY".to_owned(), &summary);
+            transaction.write(
+                format!("content/entries/{id}.index"),
+                search_index.to_bytes(),
+            );
+        }
+
+        // Write whatever changed together.
+        if self.index.dirty_entries.data.remove(&id) {
+            transaction.write(
+                format!("content/entries/{id}.json"),
+                serde_json::to_vec_pretty(self.data()).unwrap(),
+            );
+        }
+        if !transaction.is_empty() {
+            self.index
+                .storage
+                .commit(transaction)
+                .expect("error writing entry files");
+        }
     }
 }
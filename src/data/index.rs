@@ -1,6 +1,7 @@
 use std::{
-    collections::HashMap,
-    fs::{self, File},
+    collections::{HashMap, HashSet},
+    hash::Hash,
+    sync::Arc,
 };
 
 use chrono::NaiveDate;
@@ -11,8 +12,11 @@ use super::*;
 
 use crate::search;
 
+use super::storage::{Storage, Transaction};
+
 #[derive(Serialize, Deserialize)]
 struct IndexFile {
+    schema_version: u32,
     volumes: Vec<String>,
     albums: Vec<ListenedAlbum>,
     tracks: Vec<ListenedTrack>,
@@ -22,58 +26,115 @@ struct IndexFile {
 
 /// Wrapper around all journal data.
 pub struct Index {
+    pub(super) storage: Arc<dyn Storage>,
     pub(super) users: HashMap<String, UserData>,
     pub(super) volumes: IndexMap<String, VolumeData>,
     pub(super) entries: HashMap<String, EntryData>,
     pub(super) sections: HashMap<u32, SectionData>,
     pub(super) next_section_id: u32,
+    pub(super) dirty_users: HashSet<String>,
+    pub(super) dirty_volumes: Dirty<String>,
+    pub(super) dirty_entries: Dirty<String>,
+    pub(super) dirty_sections: Dirty<u32>,
     pub albums: Vec<ListenedAlbum>,
     pub tracks: Vec<ListenedTrack>,
     pub months_in_review: Vec<MonthInReview>,
 }
 
+/// Resources of one kind changed since they were last saved.
+#[derive(Default)]
+pub(super) struct Dirty<K> {
+    /// Resources whose data file is out of date.
+    pub(super) data: HashSet<K>,
+
+    /// Resources whose search index is out of date.
+    pub(super) search: HashSet<K>,
+}
+
+impl<K: Clone + Eq + Hash> Dirty<K> {
+    /// Every resource with something to save.
+    fn ids(&self) -> Vec<K> {
+        self.data.union(&self.search).cloned().collect()
+    }
+
+    /// Forget the changes to a resource that no longer needs saving.
+    pub(super) fn clear(&mut self, id: &K) {
+        self.data.remove(id);
+        self.search.remove(id);
+    }
+}
+
 impl Index {
     /// Force a save of every resource in the index.
     ///
     /// This is useful for regenerating search indices when the indexing
     /// algorithm changes.
     pub fn save_all(&mut self) {
-        let volume_ids: Vec<_> = self.volumes.keys().cloned().collect();
-        for id in volume_ids {
-            drop(self.volume_mut(id));
+        self.dirty_users.extend(self.users.keys().cloned());
+        for id in self.volumes.keys() {
+            self.dirty_volumes.data.insert(id.clone());
+            self.dirty_volumes.search.insert(id.clone());
+        }
+        for id in self.entries.keys() {
+            self.dirty_entries.data.insert(id.clone());
+            self.dirty_entries.search.insert(id.clone());
         }
+        for &id in self.sections.keys() {
+            self.dirty_sections.data.insert(id);
+            self.dirty_sections.search.insert(id);
+        }
+        self.flush();
+    }
 
-        let entry_ids: Vec<_> = self.entries.keys().cloned().collect();
-        for id in entry_ids {
+    /// Save every resource changed since it was last saved.
+    ///
+    /// Mutable wrappers save their own resource when dropped, so this only
+    /// has work to do for changes made outside of them.
+    pub fn flush(&mut self) {
+        for id in self.dirty_volumes.ids() {
+            drop(self.volume_mut(id));
+        }
+        for id in self.dirty_entries.ids() {
             drop(self.entry_mut(id));
         }
-
-        let section_ids: Vec<_> = self.sections.keys().cloned().collect();
-        for id in section_ids {
+        for id in self.dirty_sections.ids() {
             drop(self.section_mut(id));
         }
-
-        let user_ids: Vec<_> = self.users.keys().cloned().collect();
+        let user_ids: Vec<_> = self.dirty_users.iter().cloned().collect();
         for id in user_ids {
             drop(self.user_mut(id));
         }
 
+        // Anything left over no longer exists.
+        self.dirty_users.clear();
+        self.dirty_volumes = Dirty::default();
+        self.dirty_entries = Dirty::default();
+        self.dirty_sections = Dirty::default();
+
         self.save();
     }
 
-    /// Read data from the filesystem and construct and interface to the journal data.
-    pub fn init() -> Self {
-        let index_file =
-            fs::read_to_string("content/index.json").expect("error reading index file");
+    /// Read data from storage and construct and interface to the journal data.
+    ///
+    /// Data saved with an older schema is migrated first.
+    pub fn init(storage: Arc<dyn Storage>) -> Self {
+        migration::migrate(storage.as_ref()).expect("error migrating journal data");
+
+        let index_file = storage
+            .read_to_string("content/index.json")
+            .expect("error reading index file");
         let index_file: IndexFile =
             serde_json::from_str(&index_file).expect("index file invalid json");
 
-        let users = fs::read_to_string("users/users.json").expect("error reading users file");
+        let users = storage
+            .read_to_string("users/users.json")
+            .expect("error reading users file");
         let users: Vec<String> = serde_json::from_str(&users).expect("users file invalid json");
         let users = users
             .into_iter()
             .map(|u| {
-                let user = fs::read_to_string(format!("users/{u}.json"))
+                let user = storage
+                    .read_to_string(&format!("users/{u}.json"))
                     .expect(&format!("error reading user {u} file"));
                 let user = serde_json::from_str(&user).expect(&format!("user {u} invalid json"));
                 (u, user)
@@ -84,12 +145,14 @@ impl Index {
             .volumes
             .into_iter()
             .map(|v| {
-                let volume = fs::read_to_string(format!("content/volumes/{v}.json"))
+                let volume = storage
+                    .read_to_string(&format!("content/volumes/{v}.json"))
                     .expect(&format!("error reading volume {v} file"));
                 let mut volume: VolumeData =
                     serde_json::from_str(&volume).expect(&format!("volume {v} invalid json"));
                 volume.search_index = search::Index::from_bytes(
-                    &fs::read(format!("content/volumes/{v}.index"))
+                    &storage
+                        .read(&format!("content/volumes/{v}.index"))
                         .expect(&format!("error reading volume {v} index file")),
                 );
                 (v, volume)
@@ -101,12 +164,14 @@ impl Index {
             .map(|v| v.entries.iter())
             .flatten()
             .map(|e| {
-                let entry = fs::read_to_string(format!("content/entries/{e}.json"))
+                let entry = storage
+                    .read_to_string(&format!("content/entries/{e}.json"))
                     .expect(&format!("error reading entry {e} file"));
                 let mut entry: EntryData =
                     serde_json::from_str(&entry).expect(&format!("entry {e} invalid json"));
                 entry.search_index = search::Index::from_bytes(
-                    &fs::read(format!("content/entries/{e}.index"))
+                    &storage
+                        .read(&format!("content/entries/{e}.index"))
                         .expect(&format!("error reading entry {e} index file")),
                 );
                 (e.clone(), entry)
@@ -118,12 +183,14 @@ impl Index {
             .map(|e| e.sections.iter())
             .flatten()
             .map(|&s| {
-                let section = fs::read_to_string(format!("content/sections/{s}.json"))
+                let section = storage
+                    .read_to_string(&format!("content/sections/{s}.json"))
                     .expect(&format!("error reading section {s} file"));
                 let mut section: SectionData =
                     serde_json::from_str(&section).expect(&format!("section {s} invalid json"));
                 section.search_index = search::Index::from_bytes(
-                    &fs::read(format!("content/sections/{s}.index"))
+                    &storage
+                        .read(&format!("content/sections/{s}.index"))
                         .expect(&format!("error reading section {s} index file")),
                 );
                 (s, section)
@@ -131,6 +198,7 @@ impl Index {
             .collect();
 
         Index {
+            storage,
             users,
             volumes,
             entries,
@@ -139,11 +207,42 @@ impl Index {
             tracks: index_file.tracks,
             months_in_review: index_file.months_in_review,
             next_section_id: index_file.next_section_id,
+            dirty_users: HashSet::new(),
+            dirty_volumes: Dirty::default(),
+            dirty_entries: Dirty::default(),
+            dirty_sections: Dirty::default(),
         }
     }
 
+    /// Read the journal data from storage again, picking up changes made to
+    /// it outside the index.
+    pub fn reload(&mut self) {
+        *self = Index::init(self.storage.clone());
+    }
+
     pub fn save_index(&self) {
+        let mut transaction = Transaction::new();
+        self.stage_index(&mut transaction);
+        self.storage
+            .commit(transaction)
+            .expect("error writing index file");
+    }
+
+    pub(super) fn save(&self) {
+        let mut transaction = Transaction::new();
+        self.stage_index(&mut transaction);
+        let users_file =
+            serde_json::to_string_pretty(&self.users.keys().cloned().collect::<Vec<_>>())
+                .expect("error serializing users file");
+        transaction.write("users/users.json", users_file);
+        self.storage
+            .commit(transaction)
+            .expect("error writing index and users files");
+    }
+
+    pub(super) fn stage_index(&self, transaction: &mut Transaction) {
         let index_file = serde_json::to_string_pretty(&IndexFile {
+            schema_version: migration::SCHEMA_VERSION,
             volumes: self.volumes.keys().cloned().collect(),
             albums: self.albums.clone(),
             tracks: self.tracks.clone(),
@@ -151,15 +250,12 @@ impl Index {
             next_section_id: self.next_section_id,
         })
         .expect("error serializing index file");
-        fs::write("content/index.json", index_file).expect("error writing index file");
+        transaction.write("content/index.json", index_file);
     }
 
-    pub(super) fn save(&self) {
-        self.save_index();
-        let users_file =
-            serde_json::to_string_pretty(&self.users.keys().cloned().collect::<Vec<_>>())
-                .expect("error serializing users file");
-        fs::write("users/users.json", users_file).expect("error writing users file");
+    /// The storage backend the journal data is kept in.
+    pub fn storage(&self) -> &dyn Storage {
+        self.storage.as_ref()
     }
 
     /// Get the user with the specified id.
@@ -171,6 +267,44 @@ impl Index {
         }
     }
 
+    /// Get the user whose feed link has the given token.
+    pub fn user_by_feed_token(&self, token: &str) -> Option<User<'_>> {
+        self.users
+            .iter()
+            .find(|(_, u)| u.feed_token.as_deref() == Some(token))
+            .map(|(id, _)| User {
+                index: self,
+                id: id.clone(),
+            })
+    }
+
+    /// Get the user whose digest unsubscribe link has the given token.
+    pub fn user_by_digest_token(&self, token: &str) -> Option<User<'_>> {
+        self.users
+            .iter()
+            .find(|(_, u)| u.digest_token.as_deref() == Some(token))
+            .map(|(id, _)| User {
+                index: self,
+                id: id.clone(),
+            })
+    }
+
+    /// Get the user a comment mentions with `@name`.
+    ///
+    /// The name is the user's id, or their first name if no one else shares
+    /// it, in any case.
+    pub fn user_by_mention(&self, name: &str) -> Option<User<'_>> {
+        let name = name.to_lowercase();
+        if let Ok(user) = self.user(name.clone()) {
+            return Some(user);
+        }
+        let mut users = self
+            .users()
+            .filter(|u| u.first_name().to_lowercase() == name);
+        let user = users.next()?;
+        users.next().is_none().then_some(user)
+    }
+
     /// Get all the users.
     pub fn users(&self) -> impl Iterator<Item = User> {
         self.users.iter().map(|(id, u)| User {
@@ -205,9 +339,15 @@ impl Index {
             history: vec![],
             preferences: HashMap::new(),
             init: false,
+            feed_token: None,
+            followed_volumes: vec![],
+            notifications: vec![],
+            digest_token: None,
+            last_digest: None,
         };
 
         self.users.insert(id.clone(), user);
+        self.dirty_users.insert(id.clone());
 
         self.save();
 
@@ -248,6 +388,7 @@ impl Index {
                 index: self,
                 id,
                 exists: true,
+                intro: None,
             });
         }
 
@@ -259,6 +400,7 @@ impl Index {
                     index: self,
                     id,
                     exists: true,
+                    intro: None,
                 });
             }
         }
@@ -296,16 +438,15 @@ impl Index {
             search_index: search::Index::new(),
         };
 
-        // Create files.
-        File::create(format!("content/volumes/{id}.json")).unwrap();
-        File::create(format!("content/volumes/{id}.intro")).unwrap();
-        File::create(format!("content/volumes/{id}.index")).unwrap();
-
         // Insert into index.
         self.volumes.shift_insert(index, id.clone(), volume);
+        self.dirty_volumes.data.insert(id.clone());
         self.save();
 
-        Ok(self.volume_mut(id).unwrap())
+        // The volume files are written together when the wrapper is dropped.
+        let mut volume = self.volume_mut(id).unwrap();
+        volume.set_intro("");
+        Ok(volume)
     }
 
     /// Get the entry with the specified id.
@@ -388,13 +529,10 @@ impl Index {
             author,
             parent_volume: (parent_volume.id.to_owned(), parent_volume_part),
             sections: Vec::new(),
+            tags: Vec::new(),
             search_index: search::Index::new(),
         };
 
-        // Create files.
-        File::create(format!("content/entries/{id}.json")).unwrap();
-        File::create(format!("content/entries/{id}.index")).unwrap();
-
         // Insert into parent volume.
         parent_volume
             .data_mut()
@@ -406,8 +544,11 @@ impl Index {
 
         // Insert into index.
         self.entries.insert(id.clone(), entry);
+        self.dirty_entries.data.insert(id.clone());
 
-        Ok(self.entry_mut(id).unwrap())
+        let mut entry = self.entry_mut(id).unwrap();
+        entry.reindex();
+        Ok(entry)
     }
 
     /// Get the section with the specified id.
@@ -427,6 +568,37 @@ impl Index {
         })
     }
 
+    /// Get every tag in use, alphabetically, with how many entries and
+    /// sections have it.
+    pub fn tags(&self) -> Vec<(String, usize)> {
+        let mut tags: HashMap<&String, usize> = HashMap::new();
+        let entry_tags = self.entries.values().flat_map(|e| &e.tags);
+        let section_tags = self.sections.values().flat_map(|s| &s.tags);
+        for tag in entry_tags.chain(section_tags) {
+            *tags.entry(tag).or_default() += 1;
+        }
+        let mut tags: Vec<_> = tags.into_iter().map(|(t, n)| (t.clone(), n)).collect();
+        tags.sort();
+        tags
+    }
+
+    /// Get the entries that have a tag or have sections with it, in reading
+    /// order, along with their sections that have it.
+    pub fn tagged(&self, tag: &str) -> Vec<(Entry<'_>, Vec<Section<'_>>)> {
+        self.volumes
+            .values()
+            .flat_map(|v| &v.entries)
+            .filter_map(|id| {
+                let entry = Entry {
+                    index: self,
+                    id: id.clone(),
+                };
+                let sections: Vec<_> = entry.sections().filter(|s| s.has_tag(tag)).collect();
+                (entry.has_tag(tag) || !sections.is_empty()).then_some((entry, sections))
+            })
+            .collect()
+    }
+
     /// Get the section with the specified id for mutation.
     pub fn section_mut(&mut self, id: u32) -> DataResult<SectionMut> {
         if self.sections.contains_key(&id) {
@@ -434,6 +606,8 @@ impl Index {
                 index: self,
                 id,
                 exists: true,
+                content: None,
+                revisions: None,
             });
         } else {
             Err(DataError::MissingResource("section", id.to_string()))
@@ -472,30 +646,418 @@ impl Index {
             length: 0,
             lines: 0,
             perspectives: Vec::new(),
+            completed: None,
+            tags: Vec::new(),
+            publish_at: None,
+            reactions: Vec::new(),
             search_index: search::Index::new(),
         };
 
-        // Create files.
-        File::create(format!("content/sections/{id}.txt")).unwrap();
-        File::create(format!("content/sections/{id}.json")).unwrap();
-        File::create(format!("content/sections/{id}.index")).unwrap();
-
         // Insert into parent entry.
         parent_entry.data_mut().sections.insert(index_in_parent, id);
         drop(parent_entry);
 
         // Insert into index.
         self.sections.insert(id, section);
+        self.dirty_sections.data.insert(id);
 
         // Increase section id.
         self.next_section_id += 1;
         self.save();
 
-        Ok(self.section_mut(id).unwrap())
+        // The section files are written together when the wrapper is dropped.
+        let mut section = self.section_mut(id).unwrap();
+        section.content = Some(String::new());
+        section.reindex();
+        Ok(section)
     }
 
     /// Get the id for the next created section.
     pub fn next_section_id(&self) -> u32 {
         self.next_section_id
     }
+
+    /// Whether any section's scheduled time has come.
+    pub fn any_scheduled_due(&self) -> bool {
+        let now = Utc::now().timestamp();
+        self.sections
+            .values()
+            .any(|s| s.publish_at.is_some_and(|t| t <= now))
+    }
+
+    /// Mark complete the sections whose scheduled time has come, returning
+    /// their ids.
+    pub fn publish_scheduled(&mut self) -> Vec<u32> {
+        let now = Utc::now().timestamp();
+        let mut due: Vec<_> = self
+            .sections
+            .iter()
+            .filter_map(|(&id, s)| Some((s.publish_at?, id)))
+            .filter(|&(publish_at, _)| publish_at <= now)
+            .collect();
+        due.sort();
+
+        // Release them in the order they were scheduled.
+        let due: Vec<_> = due.into_iter().map(|(_, id)| id).collect();
+        for &id in &due {
+            self.section_mut(id)
+                .unwrap()
+                .set_status(section::Status::Complete);
+        }
+        due
+    }
+
+    /// Get the removed resources that can be restored, oldest first.
+    pub fn trash(&self) -> Vec<TrashItem> {
+        trash::load(self).iter().map(TrashData::item).collect()
+    }
+
+    /// Bring back a removed volume and everything that was in it.
+    ///
+    /// The volume returns to its old place unless a position is given.
+    pub fn restore_volume(
+        &mut self,
+        id: String,
+        position: Option<Position<(), String>>,
+    ) -> DataResult<VolumeMut<'_>> {
+        let mut trash_list = trash::load(self);
+        let trash_index = trash::find(&trash_list, "volume", &id)?;
+        if self.volumes.contains_key(&id) {
+            return Err(DataError::DuplicateId(id));
+        }
+
+        // Get position.
+        let index = match position {
+            Some(position) => position.resolve(self)?,
+            None => match trash_list[trash_index].location {
+                Location::Volume { index } => index.min(self.volumes.len()),
+                _ => unreachable!(),
+            },
+        };
+
+        // Move the files back.
+        let trash = trash_list.remove(trash_index);
+        self.unarchive(&trash, &trash_list);
+
+        // Insert into index.
+        let volume: VolumeData = trash::read_data(self, &format!("content/volumes/{id}.json"));
+        for entry in &volume.entries {
+            self.load_restored_entry(entry);
+        }
+        self.volumes.shift_insert(index, id.clone(), volume);
+        self.save();
+
+        // Rebuild the search indices.
+        let entry_ids = self.volumes[&id].entries.clone();
+        for entry in entry_ids {
+            self.rebuild_restored_entry(entry);
+        }
+        let mut volume = self.volume_mut(id).unwrap();
+        volume.reindex();
+        Ok(volume)
+    }
+
+    /// Bring back a removed entry and its sections.
+    ///
+    /// The entry returns to its old place unless a position is given.
+    pub fn restore_entry(
+        &mut self,
+        id: String,
+        position: Option<Position<(String, usize), String>>,
+    ) -> DataResult<EntryMut<'_>> {
+        let mut trash_list = trash::load(self);
+        let trash_index = trash::find(&trash_list, "entry", &id)?;
+        if self.entries.contains_key(&id) {
+            return Err(DataError::DuplicateId(id));
+        }
+
+        // Get position.
+        let (parent_volume, parent_volume_part, index_in_parent) = match position {
+            Some(position) => {
+                let (volume, part, index) = position.resolve(self)?;
+                (volume.id.clone(), part, index)
+            }
+            None => match &trash_list[trash_index].location {
+                Location::Entry {
+                    volume,
+                    part,
+                    index,
+                } => {
+                    let volume = self.volume(volume.clone())?;
+                    let index = (*index).min(volume.entry_count());
+                    (volume.id, *part, index)
+                }
+                _ => unreachable!(),
+            },
+        };
+
+        // Move the files back.
+        let trash = trash_list.remove(trash_index);
+        self.unarchive(&trash, &trash_list);
+
+        // Insert into index.
+        self.load_restored_entry(&id);
+        self.entries.get_mut(&id).unwrap().parent_volume =
+            (parent_volume.clone(), parent_volume_part);
+        self.dirty_entries.data.insert(id.clone());
+
+        // Insert into parent volume.
+        let mut parent_volume = self.volume_mut(parent_volume).unwrap();
+        parent_volume
+            .data_mut()
+            .entries
+            .insert(index_in_parent, id.clone());
+        let current_part_count = parent_volume.parts_count();
+        parent_volume.data_mut().volume_count = current_part_count.max(1 + parent_volume_part);
+        drop(parent_volume);
+
+        self.rebuild_restored_entry(id.clone());
+        Ok(self.entry_mut(id).unwrap())
+    }
+
+    /// Bring back a removed section.
+    ///
+    /// The section returns to its old place unless a position is given.
+    pub fn restore_section(
+        &mut self,
+        id: u32,
+        position: Option<Position<String, u32>>,
+    ) -> DataResult<SectionMut<'_>> {
+        let mut trash_list = trash::load(self);
+        let trash_index = trash::find(&trash_list, "section", &id.to_string())?;
+
+        // Get position.
+        let (parent_entry, index_in_parent) = match position {
+            Some(position) => {
+                let (entry, index) = position.resolve(self)?;
+                (entry.id.clone(), index)
+            }
+            None => match &trash_list[trash_index].location {
+                Location::Section { entry, index } => {
+                    let entry = self.entry(entry.clone())?;
+                    let index = (*index).min(entry.section_count());
+                    (entry.id, index)
+                }
+                _ => unreachable!(),
+            },
+        };
+
+        // Move the files back.
+        let trash = trash_list.remove(trash_index);
+        self.unarchive(&trash, &trash_list);
+
+        // Insert into index.
+        let mut section: SectionData =
+            trash::read_data(self, &format!("content/sections/{id}.json"));
+        section.parent_entry = parent_entry.clone();
+        self.sections.insert(id, section);
+        self.dirty_sections.data.insert(id);
+
+        // Insert into parent entry.
+        self.entry_mut(parent_entry)
+            .unwrap()
+            .data_mut()
+            .sections
+            .insert(index_in_parent, id);
+
+        // The search index is rebuilt when the wrapper is dropped.
+        let mut section = self.section_mut(id).unwrap();
+        section.reindex();
+        Ok(section)
+    }
+
+    /// Permanently delete removed resources, returning how many were deleted.
+    pub fn purge_trash(&mut self, removed_before: i64) -> usize {
+        let (purged, kept): (Vec<_>, Vec<_>) = trash::load(self)
+            .into_iter()
+            .partition(|t| t.removed < removed_before);
+        if purged.is_empty() {
+            return 0;
+        }
+
+        let mut transaction = Transaction::new();
+        for file in purged.iter().flat_map(|t| t.files.iter()) {
+            transaction.remove(&file.archived);
+        }
+        trash::stage(&kept, &mut transaction);
+        self.storage
+            .commit(transaction)
+            .expect("error purging trash");
+        purged.len()
+    }
+
+    /// Get the guest submissions, oldest first.
+    pub fn submissions(&self) -> Vec<Submission> {
+        submission::load(self)
+    }
+
+    /// Get the submission with the specified id.
+    pub fn submission(&self, id: u32) -> DataResult<Submission> {
+        let submissions = submission::load(self);
+        let index = submission::find(&submissions, id)?;
+        Ok(submissions[index].clone())
+    }
+
+    /// Save a member's submission, starting a new one if no id is given, and
+    /// optionally send it for review.
+    ///
+    /// Only the author can change a submission, and only while it is a draft
+    /// or has been sent back for changes.
+    pub fn save_submission(
+        &mut self,
+        id: Option<u32>,
+        author: String,
+        draft: Draft,
+        submit: bool,
+    ) -> DataResult<Submission> {
+        let mut submissions = submission::load(self);
+        let now = Utc::now().timestamp();
+        let index = match id {
+            Some(id) => {
+                let index = submission::find(&submissions, id)?;
+                let submission = &submissions[index];
+                if submission.author != author || !submission.editable() {
+                    return Err(DataError::MissingResource("submission", id.to_string()));
+                }
+                index
+            }
+            None => {
+                let id = submissions.iter().map(|s| 1 + s.id).max().unwrap_or(0);
+                submissions.push(Submission {
+                    id,
+                    author,
+                    draft: draft.clone(),
+                    status: submission::Status::Draft,
+                    note: String::new(),
+                    created: now,
+                    updated: now,
+                    entry: None,
+                });
+                submissions.len() - 1
+            }
+        };
+
+        let submission = &mut submissions[index];
+        submission.draft = Draft {
+            title: process_text(&draft.title),
+            description: process_text(&draft.description),
+            summary: process_text(&draft.summary),
+            content: process_text(&draft.content),
+        };
+        submission.updated = now;
+        if submit {
+            submission.status = submission::Status::Submitted;
+            submission.note = String::new();
+        }
+        let submission = submission.clone();
+        submission::save(self, &submissions);
+        Ok(submission)
+    }
+
+    /// Withdraw a member's submission that hasn't been published.
+    pub fn remove_submission(&mut self, id: u32, author: &str) -> DataResult<()> {
+        let mut submissions = submission::load(self);
+        let index = submission::find(&submissions, id)?;
+        let submission = &submissions[index];
+        if submission.author != author || submission.status == submission::Status::Approved {
+            return Err(DataError::MissingResource("submission", id.to_string()));
+        }
+        submissions.remove(index);
+        submission::save(self, &submissions);
+        Ok(())
+    }
+
+    /// Publish a submission as a new entry with its author, holding the text
+    /// in a single completed section dated today.
+    ///
+    /// The submission must be awaiting review, and its author must still
+    /// exist.
+    pub fn approve_submission(
+        &mut self,
+        id: u32,
+        position: Position<(String, usize), String>,
+    ) -> DataResult<EntryMut<'_>> {
+        let mut submissions = submission::load(self);
+        let index = submission::find_submitted(&submissions, id)?;
+        let Submission { author, draft, .. } = submissions[index].clone();
+        if !self.users.contains_key(&author) {
+            return Err(DataError::MissingResource("user", author));
+        }
+
+        let entry = self.create_entry(
+            &draft.title,
+            &draft.description,
+            &draft.summary,
+            author.clone(),
+            position,
+        )?;
+        let entry_id = entry.id().to_owned();
+        drop(entry);
+        let mut section = self.create_section(
+            None,
+            &draft.description,
+            &draft.summary,
+            Utc::now().date_naive(),
+            Position::EndOf(entry_id.clone()),
+        )?;
+        section.set_content(&draft.content, author);
+        section.set_status(section::Status::Complete);
+        drop(section);
+
+        let submission = &mut submissions[index];
+        submission.status = submission::Status::Approved;
+        submission.note = String::new();
+        submission.entry = Some(entry_id.clone());
+        submission::save(self, &submissions);
+        Ok(self.entry_mut(entry_id).unwrap())
+    }
+
+    /// Send a submission back to its author, either turned down or to be
+    /// revised, with a note explaining why.
+    ///
+    /// The submission must be awaiting review.
+    pub fn return_submission(
+        &mut self,
+        id: u32,
+        status: submission::ReturnStatus,
+        note: &str,
+    ) -> DataResult<Submission> {
+        let mut submissions = submission::load(self);
+        let index = submission::find_submitted(&submissions, id)?;
+        let submission = &mut submissions[index];
+        submission.status = status.into();
+        submission.note = process_text(note);
+        let submission = submission.clone();
+        submission::save(self, &submissions);
+        Ok(submission)
+    }
+
+    /// Move a removed resource's files back into place, and update the trash.
+    fn unarchive(&self, trash: &TrashData, trash_list: &[TrashData]) {
+        let mut transaction = Transaction::new();
+        trash.unarchive(&mut transaction);
+        trash::stage(trash_list, &mut transaction);
+        self.storage
+            .commit(transaction)
+            .expect("error restoring archived files");
+    }
+
+    /// Read a restored entry and its sections into the index.
+    fn load_restored_entry(&mut self, id: &str) {
+        let entry: EntryData = trash::read_data(self, &format!("content/entries/{id}.json"));
+        for &section in &entry.sections {
+            let section_data = trash::read_data(self, &format!("content/sections/{section}.json"));
+            self.sections.insert(section, section_data);
+        }
+        self.entries.insert(id.to_owned(), entry);
+    }
+
+    /// Write the search indices of a restored entry and its sections.
+    fn rebuild_restored_entry(&mut self, id: String) {
+        let section_ids = self.entries[&id].sections.clone();
+        for section in section_ids {
+            self.section_mut(section).unwrap().reindex();
+        }
+        self.entry_mut(id).unwrap().reindex();
+    }
 }
@@ -0,0 +1,690 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use chrono::NaiveDate;
+use indexmap::IndexMap;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use super::{section::Status, volume::Kind, *};
+
+/// The front matter of `volume.md`, whose body is the volume intro.
+#[derive(Serialize, Deserialize)]
+struct VolumeFront {
+    id: Option<String>,
+    title: String,
+    subtitle: Option<String>,
+    kind: Kind,
+    owner: String,
+}
+
+/// The front matter of `entry.md`.
+#[derive(Serialize, Deserialize)]
+struct EntryFront {
+    id: Option<String>,
+    title: String,
+    description: String,
+    summary: String,
+    author: String,
+    #[serde(default)]
+    part: usize,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    tags: Vec<String>,
+}
+
+/// The front matter of a section file, whose body is the section text.
+#[derive(Serialize, Deserialize)]
+struct SectionFront {
+    id: Option<u32>,
+    heading: Option<String>,
+    description: String,
+    summary: String,
+    date: String,
+    status: Status,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    tags: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    perspectives: Vec<u32>,
+}
+
+struct VolumeFile {
+    path: PathBuf,
+    front: VolumeFront,
+    intro: String,
+    entries: Vec<EntryFile>,
+}
+
+struct EntryFile {
+    path: PathBuf,
+    front: EntryFront,
+    sections: Vec<SectionFile>,
+}
+
+struct SectionFile {
+    path: PathBuf,
+    front: SectionFront,
+    date: NaiveDate,
+    content: String,
+}
+
+/// The ids of imported resources, in the order of the directory.
+type Arrangement = Vec<(String, Vec<(String, usize, Vec<u32>)>)>;
+
+/// The changes made by an import.
+#[derive(Default)]
+pub struct ImportSummary {
+    /// Resources that did not exist before.
+    pub created: usize,
+
+    /// Existing resources with changes.
+    pub updated: usize,
+
+    /// Resources missing from the directory, moved to the trash.
+    pub removed: usize,
+}
+
+/// Write the whole journal to a directory of Markdown files.
+///
+/// Each volume gets a directory holding `volume.md` and a directory for each
+/// entry, which holds `entry.md` and a file for each section. Names are
+/// numbered in journal order. Returns the number of sections written.
+pub fn export(index: &Index, dir: &Path) -> io::Result<usize> {
+    if dir.exists() && fs::read_dir(dir)?.next().is_some() {
+        return Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            format!("{} is not empty", dir.display()),
+        ));
+    }
+
+    let mut count = 0;
+    for (i, volume) in index.volumes().enumerate() {
+        let volume_dir = dir.join(format!("{:02}-{}", i + 1, volume.id()));
+        fs::create_dir_all(&volume_dir)?;
+        let front = VolumeFront {
+            id: Some(volume.id().to_owned()),
+            title: volume.title().to_owned(),
+            subtitle: volume.subtitle().cloned(),
+            kind: volume.kind(),
+            owner: volume.owner_id().to_owned(),
+        };
+        write_file(
+            &volume_dir.join("volume.md"),
+            &front,
+            &paragraphs(&volume.intro()),
+        )?;
+
+        for (j, entry) in volume.entries().enumerate() {
+            let entry_dir = volume_dir.join(format!("{:02}-{}", j + 1, entry.id()));
+            fs::create_dir(&entry_dir)?;
+            let front = EntryFront {
+                id: Some(entry.id().to_owned()),
+                title: entry.title().to_owned(),
+                description: entry.description().to_owned(),
+                summary: entry.summary().to_owned(),
+                author: entry.author_id().to_owned(),
+                part: entry.parent_volume_part(),
+                tags: entry.tags().to_owned(),
+            };
+            write_file(&entry_dir.join("entry.md"), &front, "")?;
+
+            for (k, section) in entry.sections().enumerate() {
+                let front = SectionFront {
+                    id: Some(section.id()),
+                    heading: section.heading().cloned(),
+                    description: section.description().to_owned(),
+                    summary: section.summary().to_owned(),
+                    date: section.date().format("%Y-%m-%d").to_string(),
+                    status: section.status(),
+                    tags: section.tags().to_owned(),
+                    perspectives: section.perspective_ids().to_owned(),
+                };
+                let path = entry_dir.join(format!("{:02}-{}.md", k + 1, section.id()));
+                write_file(&path, &front, &paragraphs(&section.content()))?;
+                count += 1;
+            }
+        }
+    }
+    Ok(count)
+}
+
+/// Replace the journal with the contents of a directory written by
+/// [`export`].
+///
+/// Resources named by an id that still exists are updated in place, keeping
+/// their comments and revision history. The rest are created, and resources
+/// missing from the directory are moved to the trash. The whole directory is
+/// read and checked before anything changes.
+pub fn import(index: &mut Index, dir: &Path) -> io::Result<ImportSummary> {
+    let volumes = read_tree(dir)?;
+    validate(index, &volumes)?;
+
+    // Bring each resource up to date, keeping track of its id.
+    let mut summary = ImportSummary::default();
+    let mut arrangement = Arrangement::new();
+    for volume in &volumes {
+        let volume_id = import_volume(index, volume, &mut summary)?;
+        let mut entries = Vec::new();
+        for entry in &volume.entries {
+            let entry_id = import_entry(index, &volume_id, entry, &mut summary)?;
+            let mut sections = Vec::new();
+            for section in &entry.sections {
+                sections.push(import_section(index, &entry_id, section, &mut summary)?);
+            }
+            entries.push((entry_id, entry.front.part, sections));
+        }
+        arrangement.push((volume_id, entries));
+    }
+    arrange(index, &arrangement);
+
+    // Remove what the directory left out, innermost first so nothing imported
+    // goes along with its old parent.
+    let entry_ids: HashSet<_> = arrangement
+        .iter()
+        .flat_map(|(_, e)| e.iter().map(|(id, ..)| id.clone()))
+        .collect();
+    let section_ids: HashSet<_> = arrangement
+        .iter()
+        .flat_map(|(_, e)| e.iter().flat_map(|(.., s)| s.iter().copied()))
+        .collect();
+    let stale_sections: Vec<_> = index
+        .sections
+        .keys()
+        .filter(|id| !section_ids.contains(id))
+        .copied()
+        .collect();
+    for id in stale_sections {
+        index.section_mut(id).unwrap().remove();
+        summary.removed += 1;
+    }
+    let stale_entries: Vec<_> = index
+        .entries
+        .keys()
+        .filter(|id| !entry_ids.contains(*id))
+        .cloned()
+        .collect();
+    for id in stale_entries {
+        index.entry_mut(id).unwrap().remove();
+        summary.removed += 1;
+    }
+    let stale_volumes: Vec<_> = index
+        .volumes
+        .keys()
+        .filter(|id| !arrangement.iter().any(|(v, _)| v == *id))
+        .cloned()
+        .collect();
+    for id in stale_volumes {
+        index.volume_mut(id).unwrap().remove();
+        summary.removed += 1;
+    }
+
+    // Drop perspectives on sections the directory didn't include.
+    let dangling: Vec<_> = index
+        .sections
+        .iter()
+        .filter(|(_, s)| s.perspectives.iter().any(|p| !section_ids.contains(p)))
+        .map(|(&id, _)| id)
+        .collect();
+    for id in dangling {
+        index
+            .section_mut(id)
+            .unwrap()
+            .data_mut()
+            .perspectives
+            .retain(|p| section_ids.contains(p));
+    }
+
+    index.flush();
+    Ok(summary)
+}
+
+fn import_volume(
+    index: &mut Index,
+    file: &VolumeFile,
+    summary: &mut ImportSummary,
+) -> io::Result<String> {
+    let front = &file.front;
+    let existing = front
+        .id
+        .as_ref()
+        .and_then(|id| index.volume(id.clone()).ok())
+        .map(|v| v.id().to_owned());
+    let mut volume = match &existing {
+        Some(id) => index.volume_mut(id.clone()).unwrap(),
+        None => {
+            summary.created += 1;
+            index
+                .create_volume(
+                    &front.title,
+                    front.subtitle.as_deref(),
+                    front.owner.clone(),
+                    Position::EndOf(()),
+                )
+                .map_err(|e| data_error(&file.path, e))?
+        }
+    };
+
+    let mut changed = false;
+    if volume.title() != process_text(&front.title) {
+        volume
+            .set_title(&front.title)
+            .map_err(|e| data_error(&file.path, e))?;
+        changed = true;
+    }
+    if volume.subtitle().cloned() != front.subtitle.as_deref().map(process_text) {
+        volume.set_subtitle(front.subtitle.as_deref());
+        changed = true;
+    }
+    if volume.kind() != front.kind {
+        volume.set_kind(front.kind.clone());
+        changed = true;
+    }
+    if volume.owner_id() != front.owner {
+        volume.data_mut().owner = front.owner.clone();
+        changed = true;
+    }
+    if volume.intro() != process_text(&file.intro) {
+        volume.set_intro(&file.intro);
+        changed = true;
+    }
+
+    if changed && existing.is_some() {
+        summary.updated += 1;
+    }
+    Ok(volume.id().to_owned())
+}
+
+fn import_entry(
+    index: &mut Index,
+    volume_id: &str,
+    file: &EntryFile,
+    summary: &mut ImportSummary,
+) -> io::Result<String> {
+    let front = &file.front;
+    let existing = front
+        .id
+        .as_ref()
+        .and_then(|id| index.entry(id.clone()).ok())
+        .map(|e| e.id().to_owned());
+    let mut entry = match &existing {
+        Some(id) => index.entry_mut(id.clone()).unwrap(),
+        None => {
+            summary.created += 1;
+            index
+                .create_entry(
+                    &front.title,
+                    &front.description,
+                    &front.summary,
+                    front.author.clone(),
+                    Position::EndOf((volume_id.to_owned(), front.part)),
+                )
+                .map_err(|e| data_error(&file.path, e))?
+        }
+    };
+
+    let mut changed = false;
+    if entry.title() != process_text(&front.title) {
+        entry
+            .set_title(&front.title)
+            .map_err(|e| data_error(&file.path, e))?;
+        changed = true;
+    }
+    if entry.description() != process_text(&front.description) {
+        entry.set_description(&front.description);
+        changed = true;
+    }
+    if entry.summary() != process_text(&front.summary) {
+        entry.set_summary(&front.summary);
+        changed = true;
+    }
+    if entry.author_id() != front.author {
+        entry.data_mut().author = front.author.clone();
+        changed = true;
+    }
+    if entry.tags() != process_tags(&front.tags) {
+        entry.set_tags(&front.tags);
+        changed = true;
+    }
+    let moved = entry.parent_volume_id() != volume_id || entry.parent_volume_part() != front.part;
+
+    if (changed || moved) && existing.is_some() {
+        summary.updated += 1;
+    }
+    Ok(entry.id().to_owned())
+}
+
+fn import_section(
+    index: &mut Index,
+    entry_id: &str,
+    file: &SectionFile,
+    summary: &mut ImportSummary,
+) -> io::Result<u32> {
+    let front = &file.front;
+    let author = index
+        .entry(entry_id.to_owned())
+        .unwrap()
+        .author_id()
+        .to_owned();
+    let existing = front.id.filter(|&id| index.section(id).is_ok());
+    let mut section = match existing {
+        Some(id) => index.section_mut(id).unwrap(),
+        None => {
+            summary.created += 1;
+            index
+                .create_section(
+                    front.heading.as_deref(),
+                    &front.description,
+                    &front.summary,
+                    file.date,
+                    Position::EndOf(entry_id.to_owned()),
+                )
+                .map_err(|e| data_error(&file.path, e))?
+        }
+    };
+
+    let mut changed = false;
+    if section.heading().cloned() != front.heading.as_deref().map(process_text) {
+        section.set_heading(front.heading.as_deref());
+        changed = true;
+    }
+    if section.description() != process_text(&front.description) {
+        section.set_description(&front.description);
+        changed = true;
+    }
+    if section.summary() != process_text(&front.summary) {
+        section.set_summary(&front.summary);
+        changed = true;
+    }
+    if section.date() != file.date {
+        section.set_date(file.date);
+        changed = true;
+    }
+    if section.status() != front.status {
+        section.set_status(front.status.clone());
+        changed = true;
+    }
+    if section.tags() != process_tags(&front.tags) {
+        section.set_tags(&front.tags);
+        changed = true;
+    }
+    // Perspectives can link to sections imported later, so dangling links
+    // are only dropped once the whole directory is in.
+    if section.perspective_ids() != front.perspectives {
+        section.data_mut().perspectives = front.perspectives.clone();
+        changed = true;
+    }
+    if section.content() != process_text(&file.content) {
+        section.set_content(&file.content, author);
+        changed = true;
+    }
+    let moved = section.parent_entry_id() != entry_id;
+
+    if (changed || moved) && existing.is_some() {
+        summary.updated += 1;
+    }
+    Ok(section.id())
+}
+
+/// Put the imported resources in the order of the directory. Anything else
+/// stays with its current parent, after the imported resources.
+fn arrange(index: &mut Index, arrangement: &Arrangement) {
+    let mut imported_entries = HashSet::new();
+    let mut imported_sections = HashSet::new();
+
+    // Parents of entries and sections.
+    for (volume_id, entries) in arrangement {
+        for (entry_id, part, sections) in entries {
+            imported_entries.insert(entry_id.clone());
+            let entry = index.entries.get_mut(entry_id).unwrap();
+            let parent_volume = (volume_id.clone(), *part);
+            if entry.parent_volume != parent_volume {
+                entry.parent_volume = parent_volume;
+                index.dirty_entries.data.insert(entry_id.clone());
+            }
+            for &section_id in sections {
+                imported_sections.insert(section_id);
+                let section = index.sections.get_mut(&section_id).unwrap();
+                if &section.parent_entry != entry_id {
+                    section.parent_entry = entry_id.clone();
+                    index.dirty_sections.data.insert(section_id);
+                }
+            }
+        }
+    }
+
+    // Sections in entries.
+    let imported: HashMap<_, _> = arrangement
+        .iter()
+        .flat_map(|(_, e)| e.iter().map(|(id, _, sections)| (id, sections)))
+        .collect();
+    for (entry_id, entry) in &mut index.entries {
+        let mut sections = imported.get(entry_id).cloned().cloned().unwrap_or_default();
+        sections.extend(
+            entry
+                .sections
+                .iter()
+                .filter(|s| !imported_sections.contains(*s)),
+        );
+        if sections != entry.sections {
+            entry.sections = sections;
+            index.dirty_entries.data.insert(entry_id.clone());
+        }
+    }
+
+    // Entries in volumes.
+    let imported: HashMap<_, _> = arrangement
+        .iter()
+        .map(|(id, entries)| (id, entries))
+        .collect();
+    for (volume_id, volume) in &mut index.volumes {
+        let mut entries: Vec<_> = match imported.get(volume_id) {
+            Some(entries) => entries.iter().map(|(id, ..)| id.clone()).collect(),
+            None => Vec::new(),
+        };
+        entries.extend(
+            volume
+                .entries
+                .iter()
+                .filter(|e| !imported_entries.contains(*e))
+                .cloned(),
+        );
+        let volume_count = entries
+            .iter()
+            .map(|e| 1 + index.entries[e].parent_volume.1)
+            .max()
+            .unwrap_or(0);
+        if entries != volume.entries || volume_count != volume.volume_count {
+            volume.entries = entries;
+            volume.volume_count = volume_count;
+            index.dirty_volumes.data.insert(volume_id.clone());
+        }
+    }
+
+    // Volumes, which are saved in the index file.
+    let mut volumes = IndexMap::new();
+    for (volume_id, _) in arrangement {
+        let volume = index.volumes.shift_remove(volume_id).unwrap();
+        volumes.insert(volume_id.clone(), volume);
+    }
+    volumes.extend(index.volumes.drain(..));
+    index.volumes = volumes;
+}
+
+/// Read and parse every file in an exported directory.
+fn read_tree(dir: &Path) -> io::Result<Vec<VolumeFile>> {
+    let mut volumes = Vec::new();
+    for volume_dir in numbered(dir, true)? {
+        let path = volume_dir.join("volume.md");
+        let (front, intro) = read_file(&path)?;
+        let mut entries = Vec::new();
+        for entry_dir in numbered(&volume_dir, true)? {
+            let path = entry_dir.join("entry.md");
+            let (front, _) = read_file(&path)?;
+            let mut sections = Vec::new();
+            for path in numbered(&entry_dir, false)? {
+                if path.file_name().is_some_and(|n| n == "entry.md") {
+                    continue;
+                }
+                let (front, content): (SectionFront, _) = read_file(&path)?;
+                let date = NaiveDate::parse_from_str(&front.date, "%Y-%m-%d")
+                    .map_err(|e| invalid(&path, format!("date: {e}")))?;
+                sections.push(SectionFile {
+                    path,
+                    front,
+                    date,
+                    content,
+                });
+            }
+            entries.push(EntryFile {
+                path,
+                front,
+                sections,
+            });
+        }
+        volumes.push(VolumeFile {
+            path,
+            front,
+            intro,
+            entries,
+        });
+    }
+    Ok(volumes)
+}
+
+/// Make sure the files name real users and no id twice.
+fn validate(index: &Index, volumes: &[VolumeFile]) -> io::Result<()> {
+    let mut volume_ids = HashSet::new();
+    let mut entry_ids = HashSet::new();
+    let mut section_ids = HashSet::new();
+    for volume in volumes {
+        if index.user(volume.front.owner.clone()).is_err() {
+            let owner = &volume.front.owner;
+            return Err(invalid(&volume.path, format!("unknown owner {owner}")));
+        }
+        if let Some(id) = &volume.front.id {
+            if !volume_ids.insert(id) {
+                return Err(invalid(&volume.path, format!("volume {id} appears twice")));
+            }
+        }
+        for entry in &volume.entries {
+            if index.user(entry.front.author.clone()).is_err() {
+                let author = &entry.front.author;
+                return Err(invalid(&entry.path, format!("unknown author {author}")));
+            }
+            if let Some(id) = &entry.front.id {
+                if !entry_ids.insert(id) {
+                    return Err(invalid(&entry.path, format!("entry {id} appears twice")));
+                }
+            }
+            for section in &entry.sections {
+                if let Some(id) = section.front.id {
+                    if !section_ids.insert(id) {
+                        return Err(invalid(
+                            &section.path,
+                            format!("section {id} appears twice"),
+                        ));
+                    }
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// The subdirectories or Markdown files of a directory, ordered by the number
+/// their names start with.
+fn numbered(dir: &Path, dirs: bool) -> io::Result<Vec<PathBuf>> {
+    let mut paths = Vec::new();
+    for item in fs::read_dir(dir)? {
+        let path = item?.path();
+        let wanted = if dirs {
+            path.is_dir()
+        } else {
+            path.extension().is_some_and(|e| e == "md")
+        };
+        if wanted {
+            paths.push(path);
+        }
+    }
+    paths.sort_by_key(|path| {
+        let name = path.file_name().unwrap().to_string_lossy().into_owned();
+        let number: String = name.chars().take_while(char::is_ascii_digit).collect();
+        (number.parse().unwrap_or(usize::MAX), name)
+    });
+    Ok(paths)
+}
+
+/// Write a Markdown file with front matter. Values are written as JSON, which
+/// YAML readers accept too.
+fn write_file<T: Serialize>(path: &Path, front: &T, body: &str) -> io::Result<()> {
+    let Value::Object(fields) = serde_json::to_value(front)? else {
+        unreachable!()
+    };
+    let mut file = String::from("---\n");
+    for (key, value) in fields {
+        if !value.is_null() {
+            file += &format!("{key}: {value}\n");
+        }
+    }
+    file += "---\n";
+    if !body.is_empty() {
+        file += &format!("\n{body}\n");
+    }
+    fs::write(path, file)
+}
+
+/// Read a Markdown file's front matter and body. Values that aren't valid
+/// JSON are taken as plain text.
+fn read_file<T: DeserializeOwned>(path: &Path) -> io::Result<(T, String)> {
+    let file = fs::read_to_string(path)?;
+    let mut lines = file.lines();
+    if lines.next() != Some("---") {
+        return Err(invalid(path, "missing front matter".to_owned()));
+    }
+
+    let mut fields = Map::new();
+    let mut closed = false;
+    for line in lines.by_ref() {
+        if line == "---" {
+            closed = true;
+            break;
+        }
+        let Some((key, value)) = line.split_once(':') else {
+            return Err(invalid(path, format!("invalid front matter line {line}")));
+        };
+        let value = value.trim();
+        let value = serde_json::from_str(value).unwrap_or_else(|_| Value::String(value.to_owned()));
+        fields.insert(key.trim().to_owned(), value);
+    }
+    if !closed {
+        return Err(invalid(path, "unterminated front matter".to_owned()));
+    }
+
+    let front =
+        serde_json::from_value(Value::Object(fields)).map_err(|e| invalid(path, e.to_string()))?;
+    let body = lines.collect::<Vec<_>>().join("\n").trim().to_owned();
+    Ok((front, body))
+}
+
+/// Separate lines with blank lines, so each is a Markdown paragraph.
+fn paragraphs(text: &str) -> String {
+    text.lines().collect::<Vec<_>>().join("\n\n")
+}
+
+fn invalid(path: &Path, message: String) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("{}: {message}", path.display()),
+    )
+}
+
+fn data_error(path: &Path, error: DataError) -> io::Error {
+    let message = match error {
+        DataError::DuplicateId(id) => format!("id {id} is already taken"),
+        DataError::MissingResource(kind, id) => format!("{kind} {id} does not exist"),
+        DataError::NotSubmitted(id, _) => format!("submission {id} is not awaiting review"),
+    };
+    invalid(path, message)
+}
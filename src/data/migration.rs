@@ -0,0 +1,140 @@
+use std::io;
+
+use chrono::{NaiveDate, NaiveTime, Utc};
+use serde_json::Value;
+
+use super::storage::{Storage, Transaction, DATA_DIRS};
+
+/// The schema version of the data written by this version of the website.
+pub const SCHEMA_VERSION: u32 = 2;
+
+/// Where the index file, which records the schema version, is kept.
+const INDEX_PATH: &str = "content/index.json";
+
+/// An upgrade of the stored data from one schema version to the next.
+struct Migration {
+    /// The schema version of the data once this migration is applied.
+    version: u32,
+
+    /// What the migration changes.
+    description: &'static str,
+
+    /// Stage the changes. The index file is passed in parsed, and is written
+    /// out along with the new schema version.
+    apply: fn(&dyn Storage, &mut Value, &mut Transaction) -> io::Result<()>,
+}
+
+/// Every migration, oldest first.
+const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        description: "record the schema version in the index file",
+        apply: |_, _, _| Ok(()),
+    },
+    Migration {
+        version: 2,
+        description: "record when complete sections were completed",
+        apply: record_completion,
+    },
+];
+
+/// The schema version of the stored data. Data from before versioning is
+/// version 0.
+pub fn schema_version(storage: &dyn Storage) -> io::Result<u32> {
+    let index_file = read_index_file(storage)?;
+    Ok(index_file["schema_version"].as_u64().unwrap_or(0) as u32)
+}
+
+/// Upgrade the stored data to the current schema version.
+///
+/// All journal data is backed up under `backups/` before anything changes.
+/// Each migration is committed on its own, so an interrupted upgrade picks up
+/// where it left off. Returns the number of migrations applied.
+pub fn migrate(storage: &dyn Storage) -> io::Result<usize> {
+    let version = schema_version(storage)?;
+    if version > SCHEMA_VERSION {
+        return Err(io::Error::other(format!(
+            "data has schema version {version}, but only up to {SCHEMA_VERSION} is supported"
+        )));
+    }
+    if version == SCHEMA_VERSION {
+        return Ok(0);
+    }
+
+    let backup = back_up(storage, &format!("schema-{version}"))?;
+    println!("Backed up data at schema version {version} to {backup}");
+
+    let mut count = 0;
+    for migration in MIGRATIONS.iter().filter(|m| m.version > version) {
+        println!(
+            "Migrating data to schema version {}: {}",
+            migration.version, migration.description
+        );
+        let mut index_file = read_index_file(storage)?;
+        let mut transaction = Transaction::new();
+        (migration.apply)(storage, &mut index_file, &mut transaction)?;
+        index_file["schema_version"] = migration.version.into();
+        transaction.write(
+            INDEX_PATH,
+            serde_json::to_vec_pretty(&index_file).expect("error serializing index file"),
+        );
+        storage.commit(transaction)?;
+        count += 1;
+    }
+    Ok(count)
+}
+
+/// Copy all journal data into a new backup directory named after `reason`,
+/// returning its path.
+pub(super) fn back_up(storage: &dyn Storage, reason: &str) -> io::Result<String> {
+    let backup = format!("backups/{reason}-{}", Utc::now().timestamp());
+    let mut transaction = Transaction::new();
+    for dir in DATA_DIRS {
+        for path in storage.list(dir)? {
+            let contents = storage.read(&path)?;
+            transaction.write(format!("{backup}/{path}"), contents);
+        }
+    }
+    storage.commit(transaction)?;
+    Ok(backup)
+}
+
+/// Complete sections are taken to have been completed the day they were added,
+/// including the ones in the trash.
+fn record_completion(
+    storage: &dyn Storage,
+    _: &mut Value,
+    transaction: &mut Transaction,
+) -> io::Result<()> {
+    let mut paths = storage.list("content/sections")?;
+    paths.retain(|p| p.ends_with(".json"));
+    paths.extend(
+        storage
+            .list("archived")?
+            .into_iter()
+            .filter(|p| p.starts_with("archived/section-")),
+    );
+
+    for path in paths {
+        let mut section: Value = serde_json::from_slice(&storage.read(&path)?)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        if section["status"] != "Complete" {
+            continue;
+        }
+        let completed = section["date"]
+            .as_str()
+            .and_then(|d| NaiveDate::parse_from_str(d, "%Y-%m-%d").ok())
+            .map(|d| d.and_time(NaiveTime::MIN).and_utc().timestamp());
+        section["completed"] = completed.into();
+        transaction.write(
+            path,
+            serde_json::to_vec_pretty(&section).expect("error serializing section file"),
+        );
+    }
+    Ok(())
+}
+
+fn read_index_file(storage: &dyn Storage) -> io::Result<Value> {
+    let index_file = storage.read(INDEX_PATH)?;
+    serde_json::from_slice(&index_file).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
@@ -10,8 +10,13 @@ pub use entry::{Entry, EntryMut};
 use history::HistoryEntry;
 pub use history::{EntryProgress, SectionProgress};
 pub use index::Index;
+pub use notification::{Event, Notification};
+use reactions::ReactionData;
+pub use reactions::{Reaction, Target};
 use regex::Regex;
 use section::SectionData;
+use trash::{Location, TrashData};
+pub use trash::TrashItem;
 pub use section::{Section, SectionMut};
 use serde::Deserialize;
 use user::UserData;
@@ -19,6 +24,12 @@ pub use user::{User, UserMut};
 use volume::VolumeData;
 pub use volume::{Volume, VolumeMut};
 pub use music::{ListenedAlbum, ListenedTrack, MonthInReview, Rating};
+use revision::RevisionData;
+pub use revision::{DiffLine, Revision};
+pub use submission::{Draft, Submission};
+
+/// Consistency checks for the journal data.
+pub mod check;
 
 /// Data structures for user comments.
 pub mod comments;
@@ -32,12 +43,42 @@ pub mod history;
 /// Data structures for the index binidng all the website's resources.
 pub mod index;
 
+/// Markdown folder export and import of the whole journal.
+pub mod markdown;
+
+/// Upgrades for journal data saved with an older schema.
+pub mod migration;
+
 /// Music review data structures.
 pub mod music;
 
+/// Data structures for the events users are told about.
+pub mod notification;
+
+/// Data structures for reactions to lines and comments.
+pub mod reactions;
+
+/// Restoring the journal from an archive zip.
+pub mod restore;
+
+/// Data structures for the saved history of section text.
+pub mod revision;
+
 /// Data structures for sections which contain the text content.
 pub mod section;
 
+/// Compressed snapshots of the journal data, kept on a rotating schedule.
+pub mod snapshot;
+
+/// Storage backends that journal data is loaded from and saved to.
+pub mod storage;
+
+/// Data structures for entries written by members for featured volumes.
+pub mod submission;
+
+/// Data structures for removed resources awaiting restoration or purging.
+pub mod trash;
+
 /// Data structures for users.
 pub mod user;
 
@@ -51,6 +92,9 @@ pub enum DataError {
 
     /// Wraps the resource type and the id of the resource that does not exist.
     MissingResource(&'static str, String),
+
+    /// Wraps the id and status of a submission that is not awaiting review.
+    NotSubmitted(u32, submission::Status),
 }
 
 pub type DataResult<T> = Result<T, DataError>;
@@ -177,6 +221,18 @@ fn create_id(name: &str) -> String {
     name.replace(' ', "-")
 }
 
+/// Turn tags into ids, dropping blank and repeated ones.
+fn process_tags(tags: &[String]) -> Vec<String> {
+    let mut processed = Vec::new();
+    for tag in tags {
+        let tag = create_id(tag.trim().trim_start_matches('#'));
+        if !tag.is_empty() && !processed.contains(&tag) {
+            processed.push(tag);
+        }
+    }
+    processed
+}
+
 fn process_text(text: &str) -> String {
     let text = text
         .replace("--", "—")
@@ -1,4 +1,4 @@
-use std::{collections::HashMap, fs, ops::Deref};
+use std::{collections::HashMap, fs, ops::Deref, path::Path};
 
 use chrono::{NaiveDate, Utc};
 use regex::Regex;
@@ -188,16 +188,20 @@ pub struct SpotifyCredentials {
 }
 
 impl SpotifyCredentials {
-    pub async fn fresh() -> Self {
-        let client_id = fs::read_to_string(".spotify_client_id")
+    pub async fn fresh(client_id_path: &Path, client_secret_path: &Path) -> Self {
+        let client_id = fs::read_to_string(client_id_path)
             .expect("Missing Spotify client ID")
             .trim()
             .to_owned();
-        let client_secret = fs::read_to_string(".spotify_client_secret")
+        let client_secret = fs::read_to_string(client_secret_path)
             .expect("Missing Spotify client secret")
             .trim()
             .to_owned();
 
+        Self::request(client_id, client_secret).await
+    }
+
+    async fn request(client_id: String, client_secret: String) -> Self {
         let request = reqwest::Client::new()
             .post("https://accounts.spotify.com/api/token")
             .header("Content-Type", "application/x-www-form-urlencoded")
@@ -231,7 +235,7 @@ impl SpotifyCredentials {
     pub async fn refresh(&mut self) -> &mut Self {
         let now = Utc::now().timestamp();
         if now > self.expires_at {
-            *self = Self::fresh().await
+            *self = Self::request(self.client_id.clone(), self.client_secret.clone()).await
         }
         self
     }
@@ -250,9 +254,7 @@ pub struct SpotifyData {
 
 impl SpotifyData {
     pub async fn refresh_file(index: &Index, access_token: String) -> Self {
-        let data = tokio::fs::read_to_string("content/spotify.json")
-            .await
-            .unwrap();
+        let data = index.storage.read_to_string("content/spotify.json").unwrap();
         let mut data: Self = serde_json::from_str(&data).unwrap();
 
         let track_regex = Regex::new("%(.+?)%").unwrap();
@@ -302,20 +304,16 @@ impl SpotifyData {
             }
         }
 
-        tokio::fs::write(
-            "content/spotify.json",
-            serde_json::to_string(&data).unwrap(),
-        )
-        .await
-        .unwrap();
+        index
+            .storage
+            .write("content/spotify.json", serde_json::to_vec(&data).unwrap())
+            .unwrap();
 
         data
     }
 
     pub async fn from_file(index: &Index, access_token: String) -> Self {
-        let data = tokio::fs::read_to_string("content/spotify.json")
-            .await
-            .unwrap();
+        let data = index.storage.read_to_string("content/spotify.json").unwrap();
         let data: Self = serde_json::from_str(&data).unwrap();
         let week_in_seconds = 7 * 24 * 60 * 60;
 
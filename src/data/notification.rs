@@ -0,0 +1,95 @@
+use serde::{Deserialize, Serialize};
+
+use super::*;
+
+/// How many notifications a user keeps. The oldest are dropped as new ones
+/// arrive.
+const NOTIFICATION_LIMIT: usize = 200;
+
+/// Something a user has been told about.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Notification {
+    /// An identifier for this notification, unique among the user's.
+    pub id: u32,
+
+    /// What happened.
+    pub event: Event,
+
+    /// When it happened.
+    pub timestamp: i64,
+
+    /// Whether the user has seen it.
+    pub read: bool,
+}
+
+/// Something that happened that users are told about.
+#[derive(Clone, PartialEq, Eq, Serialize, Deserialize, Debug)]
+pub enum Event {
+    /// The first section of an entry was marked complete.
+    Entry { entry: String },
+
+    /// Another section of an entry was marked complete.
+    Section { section: u32 },
+
+    /// Someone commented on a section the user wrote.
+    Comment {
+        section: u32,
+        line: usize,
+        author: String,
+    },
+
+    /// Someone commented in a thread the user commented in.
+    Reply {
+        section: u32,
+        line: usize,
+        author: String,
+    },
+
+    /// Someone mentioned the user in a comment.
+    Mention {
+        section: u32,
+        line: usize,
+        author: String,
+    },
+}
+
+impl Event {
+    /// The user who caused the event, if it was a person.
+    pub fn author(&self) -> Option<&str> {
+        match self {
+            Event::Comment { author, .. }
+            | Event::Reply { author, .. }
+            | Event::Mention { author, .. } => Some(author),
+            Event::Entry { .. } | Event::Section { .. } => None,
+        }
+    }
+
+    pub(super) fn author_mut(&mut self) -> Option<&mut String> {
+        match self {
+            Event::Comment { author, .. }
+            | Event::Reply { author, .. }
+            | Event::Mention { author, .. } => Some(author),
+            Event::Entry { .. } | Event::Section { .. } => None,
+        }
+    }
+}
+
+/// Tell each of the users about an event.
+pub(super) fn notify(index: &mut Index, users: Vec<String>, event: Event) {
+    let timestamp = Utc::now().timestamp();
+    for user in users {
+        let Ok(mut user) = index.user_mut(user) else {
+            continue;
+        };
+        let notifications = &mut user.data_mut().notifications;
+        let id = notifications.last().map_or(0, |n| 1 + n.id);
+        notifications.push(Notification {
+            id,
+            event: event.clone(),
+            timestamp,
+            read: false,
+        });
+        let excess = notifications.len().saturating_sub(NOTIFICATION_LIMIT);
+        notifications.drain(..excess);
+    }
+}
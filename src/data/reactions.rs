@@ -0,0 +1,62 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize)]
+pub(super) struct ReactionData {
+    pub(super) reaction: Reaction,
+    pub(super) target: Target,
+    pub(super) user: String,
+}
+
+/// A quick response to a line or comment, from a small fixed set.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum Reaction {
+    Heart,
+    Laugh,
+    Wow,
+    Sad,
+    Thanks,
+}
+
+impl Reaction {
+    /// Every reaction, in the order they are shown.
+    pub const ALL: [Reaction; 5] = [
+        Reaction::Heart,
+        Reaction::Laugh,
+        Reaction::Wow,
+        Reaction::Sad,
+        Reaction::Thanks,
+    ];
+
+    /// The name of the reaction in links.
+    pub fn name(self) -> &'static str {
+        match self {
+            Reaction::Heart => "heart",
+            Reaction::Laugh => "laugh",
+            Reaction::Wow => "wow",
+            Reaction::Sad => "sad",
+            Reaction::Thanks => "thanks",
+        }
+    }
+
+    /// The emoji the reaction is shown as.
+    pub fn emoji(self) -> &'static str {
+        match self {
+            Reaction::Heart => "❤️",
+            Reaction::Laugh => "😂",
+            Reaction::Wow => "😮",
+            Reaction::Sad => "😢",
+            Reaction::Thanks => "🙏",
+        }
+    }
+}
+
+/// What a reaction was left on.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, Debug)]
+pub enum Target {
+    /// A line of the section's text, by index.
+    Line(usize),
+
+    /// A comment in one of the section's threads, by UUID.
+    Comment(u128),
+}
@@ -0,0 +1,341 @@
+use std::{
+    collections::{BTreeMap, HashSet},
+    io::{self, Cursor, Read},
+    sync::Arc,
+};
+
+use serde::Deserialize;
+use zip::ZipArchive;
+
+use super::{
+    check::{self, Problem},
+    storage::{self, Memory, Storage, Transaction, DATA_DIRS},
+    *,
+};
+
+/// How a backup is combined with the live data.
+#[derive(Clone, Copy, PartialEq, Eq, Deserialize, Debug)]
+pub enum Mode {
+    /// Make the live data exactly what was backed up.
+    Replace,
+
+    /// Bring back the resources the live data is missing, keeping everything
+    /// it already has as it is.
+    Merge,
+}
+
+/// The resources a restore adds, changes and removes.
+#[derive(Default)]
+pub struct Changes {
+    pub added: Vec<String>,
+    pub changed: Vec<String>,
+    pub removed: Vec<String>,
+}
+
+impl Changes {
+    /// Whether the restore changes nothing.
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.changed.is_empty() && self.removed.is_empty()
+    }
+}
+
+/// Journal data read from an archive zip.
+pub struct Backup {
+    storage: Arc<Memory>,
+    problems: Vec<Problem>,
+}
+
+impl Backup {
+    /// Read an archive zip as made by the `/archive` route.
+    ///
+    /// Data saved with an older schema is migrated, and the data is checked
+    /// for problems, which have to be fixed before it can be restored.
+    pub fn read(zip: &[u8]) -> io::Result<Self> {
+        let mut archive = ZipArchive::new(Cursor::new(zip)).map_err(invalid)?;
+        let mut transaction = Transaction::new();
+        for i in 0..archive.len() {
+            let mut file = archive.by_index(i).map_err(invalid)?;
+            if file.is_dir() {
+                continue;
+            }
+            let path = file.name().to_owned();
+            let in_data_dir = DATA_DIRS
+                .iter()
+                .any(|dir| path.starts_with(&format!("{dir}/")));
+            if !in_data_dir || path.split('/').any(|p| p.is_empty() || p == "..") {
+                return Err(invalid(format!("unexpected file {path} in the archive")));
+            }
+            let mut contents = Vec::new();
+            file.read_to_end(&mut contents)?;
+            transaction.write(path, contents);
+        }
+
+        let storage = Arc::new(Memory::new());
+        storage.commit(transaction)?;
+        if !storage.exists("content/index.json") {
+            return Err(invalid("the archive holds no journal data"));
+        }
+        migration::migrate(storage.as_ref())?;
+        let problems = check::check(storage.clone(), false);
+        Ok(Self { storage, problems })
+    }
+
+    /// The problems found in the backed up data.
+    pub fn problems(&self) -> &[Problem] {
+        &self.problems
+    }
+
+    /// What restoring the backup would change, without changing anything.
+    pub fn changes(&self, index: &Index, mode: Mode) -> io::Result<Changes> {
+        let restored = self.restored(index, mode)?;
+        let (_, changes) = diff(index.storage(), restored.as_ref())?;
+        Ok(changes)
+    }
+
+    /// Restore the backup into the live data and reload the index.
+    ///
+    /// The live data is backed up under `backups/` first.
+    pub fn restore(&self, index: &mut Index, mode: Mode) -> io::Result<Changes> {
+        if !self.problems.is_empty() {
+            return Err(invalid("the backup has problems and can't be restored"));
+        }
+
+        let restored = self.restored(index, mode)?;
+        let (transaction, changes) = diff(index.storage(), restored.as_ref())?;
+        if transaction.is_empty() {
+            return Ok(changes);
+        }
+
+        let backup = migration::back_up(index.storage(), "restore")?;
+        println!("Backed up data before restoring to {backup}");
+        index.storage().commit(transaction)?;
+        index.reload();
+        Ok(changes)
+    }
+
+    /// The journal data as it would be after restoring.
+    fn restored(&self, index: &Index, mode: Mode) -> io::Result<Arc<Memory>> {
+        match mode {
+            Mode::Replace => Ok(self.storage.clone()),
+            Mode::Merge => merge(index.storage(), self.storage.clone()),
+        }
+    }
+}
+
+/// Add the resources missing from the live data to a copy of it.
+///
+/// Added entries and sections are placed after the nearest sibling that
+/// precedes them in the backup.
+fn merge(live: &dyn Storage, backup: Arc<Memory>) -> io::Result<Arc<Memory>> {
+    let merged = Arc::new(Memory::new());
+    storage::copy(live, merged.as_ref())?;
+    let mut index = Index::init(merged.clone());
+    let mut from = Index::init(backup.clone());
+    let mut transaction = Transaction::new();
+    let mut copy = |path: String| -> io::Result<()> {
+        if backup.exists(&path) {
+            transaction.write(&path, backup.read(&path)?);
+        }
+        Ok(())
+    };
+
+    // Users.
+    let new_users: Vec<_> = from
+        .users
+        .keys()
+        .filter(|u| !index.users.contains_key(*u))
+        .cloned()
+        .collect();
+    for id in new_users {
+        index
+            .users
+            .insert(id.clone(), from.users.remove(&id).unwrap());
+        index.dirty_users.insert(id);
+    }
+
+    let new_volumes: HashSet<_> = from
+        .volumes
+        .keys()
+        .filter(|v| !index.volumes.contains_key(*v))
+        .cloned()
+        .collect();
+    let new_entries: HashSet<_> = from
+        .entries
+        .keys()
+        .filter(|e| !index.entries.contains_key(*e))
+        .cloned()
+        .collect();
+    let new_sections: HashSet<_> = from
+        .sections
+        .keys()
+        .filter(|s| !index.sections.contains_key(*s))
+        .copied()
+        .collect();
+
+    // Sections, listed in their entries if those are already live.
+    for (entry_id, entry) in &from.entries {
+        for &id in &entry.sections {
+            if !new_sections.contains(&id) || new_entries.contains(entry_id) {
+                continue;
+            }
+            let live_entry = index.entries.get_mut(entry_id).unwrap();
+            insert_after_sibling(&mut live_entry.sections, &entry.sections, id);
+            index.dirty_entries.data.insert(entry_id.clone());
+        }
+    }
+    for &id in &new_sections {
+        index
+            .sections
+            .insert(id, from.sections.remove(&id).unwrap());
+        index.dirty_sections.data.insert(id);
+        copy(format!("content/sections/{id}.txt"))?;
+        copy(format!("content/sections/{id}.index"))?;
+        copy(revision::path(id))?;
+    }
+    index.next_section_id = index.next_section_id.max(from.next_section_id);
+
+    // Entries, listed in their volumes if those are already live.
+    for (volume_id, volume) in &from.volumes {
+        for id in &volume.entries {
+            if !new_entries.contains(id) || new_volumes.contains(volume_id) {
+                continue;
+            }
+            let part = from.entries[id].parent_volume.1;
+            let live_volume = index.volumes.get_mut(volume_id).unwrap();
+            insert_after_sibling(&mut live_volume.entries, &volume.entries, id.clone());
+            live_volume.volume_count = live_volume.volume_count.max(part + 1);
+            index.dirty_volumes.data.insert(volume_id.clone());
+        }
+    }
+    for id in &new_entries {
+        let mut entry = from.entries.remove(id).unwrap();
+        entry.sections.retain(|s| new_sections.contains(s));
+        index.entries.insert(id.clone(), entry);
+        index.dirty_entries.data.insert(id.clone());
+        copy(format!("content/entries/{id}.index"))?;
+    }
+
+    // Volumes.
+    let volume_order: Vec<_> = from.volumes.keys().cloned().collect();
+    for id in &volume_order {
+        if !new_volumes.contains(id) {
+            continue;
+        }
+        let mut volume = from.volumes.shift_remove(id).unwrap();
+        volume.entries.retain(|e| new_entries.contains(e));
+        let position = volume_order
+            .iter()
+            .take_while(|v| *v != id)
+            .filter_map(|v| index.volumes.get_index_of(v))
+            .last()
+            .map_or(0, |i| i + 1);
+        index.volumes.shift_insert(position, id.clone(), volume);
+        index.dirty_volumes.data.insert(id.clone());
+        copy(format!("content/volumes/{id}.intro"))?;
+        copy(format!("content/volumes/{id}.index"))?;
+    }
+
+    // Images.
+    for path in backup.list("content/images")? {
+        if !merged.exists(&path) {
+            copy(path)?;
+        }
+    }
+
+    merged.commit(transaction)?;
+    index.flush();
+    Ok(merged)
+}
+
+/// Insert an item into a list after the nearest item that precedes it in
+/// `order` and is already in the list, or at the start.
+fn insert_after_sibling<T: Clone + PartialEq>(list: &mut Vec<T>, order: &[T], item: T) {
+    let position = order
+        .iter()
+        .take_while(|i| **i != item)
+        .filter_map(|i| list.iter().position(|l| l == i))
+        .last()
+        .map_or(0, |i| i + 1);
+    list.insert(position, item);
+}
+
+/// Stage the file operations that turn `live` into `restored`, and describe
+/// them by resource.
+fn diff(live: &dyn Storage, restored: &dyn Storage) -> io::Result<(Transaction, Changes)> {
+    let live_files = files(live)?;
+    let restored_files = files(restored)?;
+    let mut transaction = Transaction::new();
+
+    // Whether each resource is live, restored, and changed.
+    let mut resources: BTreeMap<String, (bool, bool, bool)> = BTreeMap::new();
+    for (path, contents) in &restored_files {
+        let differs = live_files.get(path) != Some(contents);
+        if differs {
+            transaction.write(path, contents.clone());
+        }
+        if let Some(resource) = resource(path) {
+            let state = resources.entry(resource).or_default();
+            state.0 |= live_files.contains_key(path);
+            state.1 = true;
+            state.2 |= differs;
+        }
+    }
+    for path in live_files.keys() {
+        if restored_files.contains_key(path) {
+            continue;
+        }
+        transaction.remove(path);
+        if let Some(resource) = resource(path) {
+            let state = resources.entry(resource).or_default();
+            state.0 = true;
+            state.2 = true;
+        }
+    }
+
+    let mut changes = Changes::default();
+    for (resource, (live, restored, changed)) in resources {
+        match (live, restored) {
+            (false, true) => changes.added.push(resource),
+            (true, false) => changes.removed.push(resource),
+            _ if changed => changes.changed.push(resource),
+            _ => {}
+        }
+    }
+    Ok((transaction, changes))
+}
+
+/// Every journal data file, by path.
+fn files(storage: &dyn Storage) -> io::Result<BTreeMap<String, Vec<u8>>> {
+    let mut files = BTreeMap::new();
+    for dir in DATA_DIRS {
+        for path in storage.list(dir)? {
+            let contents = storage.read(&path)?;
+            files.insert(path, contents);
+        }
+    }
+    Ok(files)
+}
+
+/// The resource a data file belongs to, if it is worth reporting.
+///
+/// The users file only lists the users, and changes along with them.
+fn resource(path: &str) -> Option<String> {
+    let (dir, file) = path.rsplit_once('/')?;
+    let name = file.split('.').next().unwrap_or(file);
+    let resource = match dir {
+        "content/volumes" => format!("volume {name}"),
+        "content/entries" => format!("entry {name}"),
+        "content/sections" | "content/revisions" => format!("section {name}"),
+        "content/images" => format!("image {name}"),
+        "users" if file == "users.json" => return None,
+        "users" => format!("user {name}"),
+        "content" if file == "index.json" => "volume order and music reviews".to_owned(),
+        "archived" => "trash".to_owned(),
+        _ => path.to_owned(),
+    };
+    Some(resource)
+}
+
+fn invalid(error: impl Into<Box<dyn std::error::Error + Send + Sync>>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, error)
+}
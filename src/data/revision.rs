@@ -0,0 +1,187 @@
+use serde::{Deserialize, Serialize};
+use similar::{Algorithm, ChangeTag, DiffOp, TextDiff};
+
+use super::*;
+
+#[derive(Clone, Serialize, Deserialize)]
+pub(super) struct RevisionData {
+    pub(super) timestamp: i64,
+    pub(super) author: String,
+    pub(super) diff: Vec<Edit>,
+}
+
+impl RevisionData {
+    /// The number of lines added and removed by this revision.
+    pub(super) fn line_counts(&self) -> (usize, usize) {
+        self.diff
+            .iter()
+            .fold((0, 0), |(added, removed), edit| match edit {
+                Edit::Keep(_) => (added, removed),
+                Edit::Delete(count) => (added, removed + count),
+                Edit::Insert(lines) => (added + lines.len(), removed),
+            })
+    }
+}
+
+/// One step of a line diff from the previous revision's text.
+#[derive(Clone, Serialize, Deserialize)]
+pub(super) enum Edit {
+    /// Keep this many lines from the previous text.
+    Keep(usize),
+
+    /// Skip this many lines from the previous text.
+    Delete(usize),
+
+    /// Add these lines.
+    Insert(Vec<String>),
+}
+
+/// A saved version of a section's text content.
+pub struct Revision<'index> {
+    /// The position of this revision in the section's history, starting at 0.
+    pub number: usize,
+
+    /// When this revision was saved.
+    pub timestamp: i64,
+
+    /// The user who saved this revision, if they still exist.
+    pub author: Option<User<'index>>,
+
+    /// The number of lines added since the previous revision.
+    pub added: usize,
+
+    /// The number of lines removed since the previous revision.
+    pub removed: usize,
+}
+
+/// A line in a comparison between two revisions.
+pub enum DiffLine {
+    /// A line both revisions share.
+    Unchanged(String),
+
+    /// A line only the newer revision has.
+    Added(String),
+
+    /// A line only the older revision has.
+    Removed(String),
+
+    /// A run of this many unchanged lines, left out for brevity.
+    Skipped(usize),
+}
+
+/// The storage path of a section's revision history.
+pub(super) fn path(section_id: u32) -> String {
+    format!("content/revisions/{section_id}.json")
+}
+
+/// Load a section's revision history, which is empty if it has never been
+/// saved with revisions.
+pub(super) fn load(index: &Index, section_id: u32) -> Vec<RevisionData> {
+    match index.storage.read(&path(section_id)) {
+        Ok(revisions) => {
+            serde_json::from_slice(&revisions).expect("error parsing section revisions")
+        }
+        Err(_) => Vec::new(),
+    }
+}
+
+/// Split text into lines, keeping blank lines. Empty text has no lines.
+fn lines(text: &str) -> Vec<&str> {
+    if text.is_empty() {
+        Vec::new()
+    } else {
+        text.split('\n').collect()
+    }
+}
+
+/// Compute the edits that turn one text into another.
+pub(super) fn diff(old: &str, new: &str) -> Vec<Edit> {
+    let old_lines = lines(old);
+    let new_lines = lines(new);
+    let inserted = |index: usize, len: usize| {
+        new_lines[index..index + len]
+            .iter()
+            .map(|&l| l.to_owned())
+            .collect()
+    };
+
+    let mut edits = Vec::new();
+    for op in similar::capture_diff_slices(Algorithm::Myers, &old_lines, &new_lines) {
+        match op {
+            DiffOp::Equal { len, .. } => edits.push(Edit::Keep(len)),
+            DiffOp::Delete { old_len, .. } => edits.push(Edit::Delete(old_len)),
+            DiffOp::Insert {
+                new_index, new_len, ..
+            } => edits.push(Edit::Insert(inserted(new_index, new_len))),
+            DiffOp::Replace {
+                old_len,
+                new_index,
+                new_len,
+                ..
+            } => {
+                edits.push(Edit::Delete(old_len));
+                edits.push(Edit::Insert(inserted(new_index, new_len)));
+            }
+        }
+    }
+    edits
+}
+
+/// Apply a revision's edits to the text of the revision before it.
+pub(super) fn apply(old: &str, diff: &[Edit]) -> String {
+    let mut old_lines = lines(old).into_iter();
+    let mut new_lines = Vec::new();
+    for edit in diff {
+        match edit {
+            Edit::Keep(count) => new_lines.extend(old_lines.by_ref().take(*count)),
+            Edit::Delete(count) => {
+                old_lines.by_ref().take(*count).for_each(drop);
+            }
+            Edit::Insert(lines) => new_lines.extend(lines.iter().map(String::as_str)),
+        }
+    }
+    new_lines.join("\n")
+}
+
+/// The text content as of a revision.
+pub(super) fn content_at(revisions: &[RevisionData], number: usize) -> String {
+    revisions[..=number]
+        .iter()
+        .fold(String::new(), |content, revision| {
+            apply(&content, &revision.diff)
+        })
+}
+
+/// Compare two texts line by line, leaving out unchanged lines that are far
+/// from any change.
+pub fn line_diff(old: &str, new: &str) -> Vec<DiffLine> {
+    let old_lines = lines(old);
+    let new_lines = lines(new);
+    let diff = TextDiff::configure()
+        .algorithm(Algorithm::Myers)
+        .diff_slices(&old_lines, &new_lines);
+
+    let mut changes = Vec::new();
+    let mut last_line = 0;
+    for group in diff.grouped_ops(2) {
+        let first_line = group[0].old_range().start;
+        if first_line > last_line {
+            changes.push(DiffLine::Skipped(first_line - last_line));
+        }
+        for op in &group {
+            for change in diff.iter_changes(op) {
+                let line = change.value().to_string();
+                changes.push(match change.tag() {
+                    ChangeTag::Equal => DiffLine::Unchanged(line),
+                    ChangeTag::Insert => DiffLine::Added(line),
+                    ChangeTag::Delete => DiffLine::Removed(line),
+                });
+            }
+        }
+        last_line = group.last().unwrap().old_range().end;
+    }
+    if !changes.is_empty() && old_lines.len() > last_line {
+        changes.push(DiffLine::Skipped(old_lines.len() - last_line));
+    }
+    changes
+}
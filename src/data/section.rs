@@ -1,16 +1,13 @@
-use std::{
-    collections::{HashMap, HashSet},
-    fs::{self, File},
-};
+use std::collections::{HashMap, HashSet};
 
-use chrono::{NaiveDate, Utc};
+use chrono::{DateTime, NaiveDate, Utc};
 use levenshtein::levenshtein;
 use rand::Rng;
 use serde::{Deserialize, Serialize};
 
 use crate::search;
 
-use super::*;
+use super::{storage::Transaction, *};
 
 #[derive(Serialize, Deserialize)]
 pub(super) struct SectionData {
@@ -24,6 +21,14 @@ pub(super) struct SectionData {
     pub(super) length: usize,
     pub(super) lines: usize,
     pub(super) perspectives: Vec<u32>,
+    #[serde(default)]
+    pub(super) completed: Option<i64>,
+    #[serde(default)]
+    pub(super) tags: Vec<String>,
+    #[serde(default)]
+    pub(super) publish_at: Option<i64>,
+    #[serde(default)]
+    pub(super) reactions: Vec<ReactionData>,
 
     #[serde(skip)]
     pub(super) search_index: search::Index,
@@ -75,6 +80,16 @@ macro_rules! immut_fns {
             &self.data().summary
         }
 
+        /// The section's tags, which group it with content on the same subject.
+        pub fn tags(&self) -> &[String] {
+            &self.data().tags
+        }
+
+        /// Whether the section has the given tag.
+        pub fn has_tag(&self, tag: &str) -> bool {
+            self.data().tags.iter().any(|t| t == tag)
+        }
+
         /// The section completion status.
         pub fn status(&self) -> Status {
             self.data().status.clone()
@@ -85,6 +100,16 @@ macro_rules! immut_fns {
             NaiveDate::parse_from_str(&self.data().date, "%Y-%m-%d").unwrap()
         }
 
+        /// When the section was last marked complete, if it is complete.
+        pub fn completed(&self) -> Option<DateTime<Utc>> {
+            DateTime::from_timestamp(self.data().completed?, 0)
+        }
+
+        /// When the section is scheduled to be marked complete, if it is.
+        pub fn publish_at(&self) -> Option<DateTime<Utc>> {
+            DateTime::from_timestamp(self.data().publish_at?, 0)
+        }
+
         /// The comment thread associated with the specified line.
         ///
         /// This will return an empty thread if the line doesn't have any comments.
@@ -101,12 +126,34 @@ macro_rules! immut_fns {
                     show: c.show,
                     author: self.index.user(c.author.clone()).unwrap(),
                     timestamp: c.timestamp,
+                    reply_to: c.reply_to,
                 })
                 .collect();
 
             Thread { line, comments }
         }
 
+        /// The reactions left on a line or comment, with the users who left
+        /// them, in the order of [`Reaction::ALL`].
+        ///
+        /// Reactions nobody left are omitted.
+        pub fn reactions(&self, target: Target) -> Vec<(Reaction, Vec<User<'_>>)> {
+            Reaction::ALL
+                .into_iter()
+                .map(|reaction| {
+                    let users = self
+                        .data()
+                        .reactions
+                        .iter()
+                        .filter(|r| r.target == target && r.reaction == reaction)
+                        .map(|r| self.index.user(r.user.clone()).unwrap())
+                        .collect();
+                    (reaction, users)
+                })
+                .filter(|(_, users): &(_, Vec<_>)| !users.is_empty())
+                .collect()
+        }
+
         /// Get all the threads in this section.
         pub fn threads(&self) -> Vec<Thread> {
             (0..self.lines())
@@ -149,25 +196,82 @@ macro_rules! immut_fns {
             self.data().lines
         }
 
-        /// The text content of this section.
-        pub fn content(&self) -> String {
-            fs::read_to_string(format!("content/sections/{}.txt", self.id)).unwrap()
-        }
-
         /// The ids of the perspectives on this section.
         pub fn perspective_ids(&self) -> &[u32] {
             &self.data().perspectives
         }
 
+        /// The other perspectives on this section: the sections it links to,
+        /// then the sections that link to it.
+        pub fn perspectives(&self) -> Vec<Section<'_>> {
+            let mut linking: Vec<_> = self
+                .index
+                .sections
+                .iter()
+                .filter(|(_, s)| s.perspectives.contains(&self.id))
+                .map(|(&id, _)| id)
+                .collect();
+            linking.sort();
+
+            let mut ids = self.data().perspectives.clone();
+            for id in linking {
+                if !ids.contains(&id) {
+                    ids.push(id);
+                }
+            }
+            ids.into_iter()
+                .filter_map(|id| self.index.section(id).ok())
+                .collect()
+        }
+
         /// Get the search index for this section.
         pub fn search_index(&self) -> &search::Index {
             &self.data().search_index
         }
+
+        /// The saved revisions of this section's text content, oldest first.
+        pub fn revisions(&self) -> Vec<Revision<'_>> {
+            self.revision_data()
+                .into_iter()
+                .enumerate()
+                .map(|(number, revision)| {
+                    let (added, removed) = revision.line_counts();
+                    Revision {
+                        number,
+                        timestamp: revision.timestamp,
+                        author: self.index.user(revision.author).ok(),
+                        added,
+                        removed,
+                    }
+                })
+                .collect()
+        }
+
+        /// The text content of this section as of a revision.
+        pub fn revision_content(&self, number: usize) -> DataResult<String> {
+            let revisions = self.revision_data();
+            if number >= revisions.len() {
+                return Err(DataError::MissingResource("revision", number.to_string()));
+            }
+            Ok(revision::content_at(&revisions, number))
+        }
     };
 }
 
 impl Section<'_> {
     immut_fns!();
+
+    /// The text content of this section.
+    pub fn content(&self) -> String {
+        self.index
+            .storage
+            .read_to_string(&format!("content/sections/{}.txt", self.id))
+            .unwrap()
+    }
+
+    fn revision_data(&self) -> Vec<RevisionData> {
+        revision::load(self.index, self.id)
+    }
 }
 
 impl<'index> Section<'index> {
@@ -189,13 +293,26 @@ pub struct SectionMut<'index> {
     pub(super) index: &'index mut Index,
     pub(super) id: u32,
     pub(super) exists: bool,
+
+    /// Text content set since the last save, written out on drop.
+    pub(super) content: Option<String>,
+
+    /// Revision history including revisions since the last save, written out
+    /// on drop.
+    pub(super) revisions: Option<Vec<RevisionData>>,
 }
 
 impl SectionMut<'_> {
     pub(super) fn data_mut(&mut self) -> &mut SectionData {
+        self.index.dirty_sections.data.insert(self.id);
         self.index.sections.get_mut(&self.id).unwrap()
     }
 
+    /// Rebuild the search index when the wrapper is dropped.
+    pub fn reindex(&mut self) {
+        self.index.dirty_sections.search.insert(self.id);
+    }
+
     pub fn as_immut(&self) -> Section {
         Section {
             index: &self.index,
@@ -212,24 +329,134 @@ impl SectionMut<'_> {
 
     immut_fns!();
 
+    /// The text content of this section, including unsaved changes.
+    pub fn content(&self) -> String {
+        match &self.content {
+            Some(content) => content.clone(),
+            None => self
+                .index
+                .storage
+                .read_to_string(&format!("content/sections/{}.txt", self.id))
+                .unwrap(),
+        }
+    }
+
+    fn revision_data(&self) -> Vec<RevisionData> {
+        match &self.revisions {
+            Some(revisions) => revisions.clone(),
+            None => revision::load(self.index, self.id),
+        }
+    }
+
     /// Set the section heading.
     pub fn set_heading(&mut self, heading: Option<&str>) {
         self.data_mut().heading = heading.map(|h| process_text(&h));
+        self.reindex();
     }
 
     /// Set the section description (brief explanation).
     pub fn set_description(&mut self, description: &str) {
         self.data_mut().description = process_text(description);
+        self.reindex();
     }
 
     /// Set the section summary (longer explanation).
     pub fn set_summary(&mut self, summary: &str) {
         self.data_mut().summary = process_text(summary);
+        self.reindex();
+    }
+
+    /// Set the section's tags.
+    pub fn set_tags(&mut self, tags: &[String]) {
+        self.data_mut().tags = process_tags(tags);
+    }
+
+    /// Link another section as a perspective on this one.
+    ///
+    /// Links show on both sections, so nothing changes if they are already
+    /// linked either way.
+    pub fn add_perspective(&mut self, id: u32) -> DataResult<()> {
+        let linked = self.index.section(id)?.perspective_ids().contains(&self.id)
+            || self.perspective_ids().contains(&id);
+        if id != self.id && !linked {
+            self.data_mut().perspectives.push(id);
+        }
+        Ok(())
+    }
+
+    /// Unlink another section as a perspective on this one, whichever way
+    /// they were linked.
+    pub fn remove_perspective(&mut self, id: u32) -> DataResult<()> {
+        let this = self.id;
+        let mut other = self.index.section_mut(id)?;
+        if other.perspective_ids().contains(&this) {
+            other.data_mut().perspectives.retain(|&p| p != this);
+        }
+        drop(other);
+
+        if self.perspective_ids().contains(&id) {
+            self.data_mut().perspectives.retain(|&p| p != id);
+        }
+        Ok(())
     }
 
     /// Set the section status.
+    ///
+    /// Marking a section complete records when, so readers can be told about
+    /// it. Setting the status by hand cancels any scheduled publishing.
     pub fn set_status(&mut self, status: Status) {
-        self.data_mut().status = status;
+        let data = self.data_mut();
+        let released = status == Status::Complete && data.status != Status::Complete;
+        if status != Status::Complete {
+            data.completed = None;
+        } else if released {
+            data.completed = Some(Utc::now().timestamp());
+        }
+        data.status = status;
+        data.publish_at = None;
+
+        if released {
+            self.announce();
+        }
+    }
+
+    /// Tell readers the section is out. If it's the first of its entry's
+    /// sections to be, they're told about the entry instead, since until now
+    /// there was nothing in it to read.
+    fn announce(&mut self) {
+        let entry = self.parent_entry();
+        let first = !entry
+            .sections()
+            .any(|s| s.id() != self.id && s.status() == Status::Complete);
+        let event = if first {
+            Event::Entry {
+                entry: entry.id().to_owned(),
+            }
+        } else {
+            Event::Section { section: self.id }
+        };
+        let author = entry.author_id().to_owned();
+        let readers = self
+            .index
+            .users
+            .keys()
+            .filter(|&u| *u != author)
+            .cloned()
+            .collect();
+        notification::notify(self.index, readers, event);
+    }
+
+    /// Schedule the section to be marked complete at the specified time, or
+    /// cancel its schedule.
+    ///
+    /// Returns [`false`] if the section is already complete, since there is
+    /// nothing to schedule.
+    pub fn schedule(&mut self, publish_at: Option<DateTime<Utc>>) -> bool {
+        if publish_at.is_some() && self.status() == Status::Complete {
+            return false;
+        }
+        self.data_mut().publish_at = publish_at.map(|t| t.timestamp());
+        true
     }
 
     /// Set the section creation date.
@@ -239,32 +466,156 @@ impl SectionMut<'_> {
 
     /// Add a comment by a user to the thread at the specified line.
     pub fn add_comment(&mut self, user: String, line: usize, content: &str) -> bool {
+        self.push_comment(user, line, content, None)
+    }
+
+    /// Add a comment by a user replying to another comment by its UUID, in
+    /// the same thread.
+    ///
+    /// Returns [`false`] if the comment replied to doesn't exist.
+    pub fn add_reply(&mut self, user: String, uuid: u128, content: &str) -> bool {
+        let Some(line) = self
+            .data()
+            .comments
+            .iter()
+            .find(|c| c.uuid == uuid)
+            .map(|c| c.line)
+        else {
+            return false;
+        };
+        self.push_comment(user, line, content, Some(uuid))
+    }
+
+    fn push_comment(
+        &mut self,
+        user: String,
+        line: usize,
+        content: &str,
+        reply_to: Option<u128>,
+    ) -> bool {
         if self.index.user(user.to_owned()).is_err() {
             return false;
         }
-        
+
+        let content = process_text(content);
+        let mut mentioned = comments::mentions(self.index, &content);
+        mentioned.retain(|u| *u != user);
         self.data_mut().comments.push(CommentData {
             uuid: rand::thread_rng().gen(),
-            content: vec![process_text(content)],
+            content: vec![content],
             show: true,
             line,
-            author: user,
+            author: user.clone(),
             timestamp: Utc::now().timestamp(),
+            reply_to,
+            mentioned: mentioned.clone(),
         });
 
+        // Let the mentioned users know.
+        notification::notify(
+            self.index,
+            mentioned.clone(),
+            Event::Mention {
+                section: self.id,
+                line,
+                author: user.clone(),
+            },
+        );
+
+        // Let the others in the thread know, and the writer of the section,
+        // unless they were just told about the mention.
+        let mut participants: Vec<_> = self
+            .data()
+            .comments
+            .iter()
+            .filter(|c| c.line == line && c.author != user && !mentioned.contains(&c.author))
+            .map(|c| c.author.clone())
+            .collect();
+        participants.sort();
+        participants.dedup();
+        let writer = self.parent_entry().author_id().to_owned();
+        let writer_in_thread = participants.contains(&writer);
+        notification::notify(
+            self.index,
+            participants,
+            Event::Reply {
+                section: self.id,
+                line,
+                author: user.clone(),
+            },
+        );
+        if writer != user && !writer_in_thread && !mentioned.contains(&writer) {
+            notification::notify(
+                self.index,
+                vec![writer],
+                Event::Comment {
+                    section: self.id,
+                    line,
+                    author: user,
+                },
+            );
+        }
+
         true
     }
 
-    /// Edit a comment's contents by its UUID.
+    /// Add a user's reaction to a line or comment, or take it back if they
+    /// already left it.
     ///
-    /// The comment's past contents are preserved.
-    pub fn edit_comment(&mut self, uuid: u128, content: &str) {
-        for comment in &mut self.data_mut().comments {
-            if comment.uuid == uuid {
-                comment.content.push(process_text(content));
-                return;
-            }
+    /// Returns [`false`] if the line or comment doesn't exist.
+    pub fn toggle_reaction(&mut self, user: String, target: Target, reaction: Reaction) -> bool {
+        let exists = match target {
+            Target::Line(line) => line < self.lines(),
+            Target::Comment(uuid) => self.data().comments.iter().any(|c| c.uuid == uuid),
+        };
+        if !exists || self.index.user(user.clone()).is_err() {
+            return false;
         }
+
+        let reactions = &mut self.data_mut().reactions;
+        let count = reactions.len();
+        reactions.retain(|r| !(r.target == target && r.reaction == reaction && r.user == user));
+        if reactions.len() == count {
+            reactions.push(ReactionData {
+                reaction,
+                target,
+                user,
+            });
+        }
+        true
+    }
+
+    /// Edit a user's comment's contents by its UUID.
+    ///
+    /// The comment's past contents are preserved. Users newly mentioned in the
+    /// comment are told about it. Returns [`false`] if the user has no such
+    /// comment.
+    pub fn edit_comment(&mut self, user: &str, uuid: u128, content: &str) -> bool {
+        let content = process_text(content);
+        let mentions = comments::mentions(self.index, &content);
+        let section = self.id;
+        let Some(comment) = self
+            .data_mut()
+            .comments
+            .iter_mut()
+            .find(|c| c.uuid == uuid && c.author == user)
+        else {
+            return false;
+        };
+        comment.content.push(content);
+
+        let new: Vec<_> = mentions
+            .into_iter()
+            .filter(|u| *u != comment.author && !comment.mentioned.contains(u))
+            .collect();
+        comment.mentioned.extend(new.iter().cloned());
+        let event = Event::Mention {
+            section,
+            line: comment.line,
+            author: comment.author.clone(),
+        };
+        notification::notify(self.index, new, event);
+        true
     }
 
     /// Remove a comment by its UUID from its thread.
@@ -294,26 +645,55 @@ impl SectionMut<'_> {
             .unwrap()
     }
 
-    /// Set the text content of the section.
-    pub fn set_content(&mut self, content: &str) {
+    /// Set the text content of the section, saved as a new revision by the
+    /// specified user.
+    pub fn set_content(&mut self, content: &str, author: String) {
+        self.replace_content(process_text(content), author);
+    }
+
+    /// Restore the text content of an earlier revision, saved as a new
+    /// revision by the specified user.
+    pub fn restore_revision(&mut self, number: usize, author: String) -> DataResult<()> {
+        let content = self.revision_content(number)?;
+        self.replace_content(content, author);
+        Ok(())
+    }
+
+    fn replace_content(&mut self, content: String, author: String) {
         // Get the lines of the old content.
         let old_content = self.content();
+        if content == old_content {
+            return;
+        }
         let old_lines: Vec<_> = old_content.lines().collect();
 
-        // Format and write the content.
-        let content = process_text(&content);
-        fs::write(format!("content/sections/{}.txt", self.id), &content);
+        // The content is written alongside the section data on drop.
         let new_lines: Vec<_> = content.lines().collect();
         self.data_mut().lines = new_lines.len();
+        self.reindex();
+
+        // Collect the line numbers that have threads or reactions.
+        let thread_lines: HashSet<_> = self
+            .data()
+            .comments
+            .iter()
+            .map(|c| c.line)
+            .chain(self.data().reactions.iter().filter_map(|r| match r.target {
+                Target::Line(line) => Some(line),
+                Target::Comment(_) => None,
+            }))
+            .collect();
 
-        // Collect the line numbers that have threads.
-        let thread_lines: HashSet<_> = self.data().comments.iter().map(|c| c.line).collect();
-
-        // Map the old lines to the new lines.
-        let mut line_map: HashMap<_, _> = thread_lines
+        // Map the old lines to the new lines. With no new lines there is
+        // nowhere to move threads to, so they stay where they are.
+        let last_line = new_lines.len().saturating_sub(1);
+        let line_map: HashMap<_, _> = thread_lines
             .into_iter()
+            .filter(|_| !new_lines.is_empty())
             .map(|old_line_number| {
-                let old_line = old_lines[old_line_number];
+                let Some(&old_line) = old_lines.get(old_line_number) else {
+                    return (old_line_number, old_line_number.min(last_line));
+                };
                 // First, test that the line did not move.
                 if new_lines.len() > old_line_number && old_line == new_lines[old_line_number] {
                     return (old_line_number, old_line_number);
@@ -332,21 +712,21 @@ impl SectionMut<'_> {
                     .filter(|(_, &l)| !l.starts_with('/'))
                     .map(|(i, &l)| (i, levenshtein(l, old_line)))
                     .collect();
-                let closest_pair = lines_and_distances
-                    .iter()
-                    .min_by_key(|&(_, dist)| dist)
-                    .unwrap();
+                let closest_pair = lines_and_distances.iter().min_by_key(|&(_, dist)| dist);
 
                 // If the closest line is less than half the length of the old line in distance
                 // away, then it's likely this is what it changed to.
-                if closest_pair.1 < old_line.len() / 2 {
-                    return (old_line_number, closest_pair.0);
+                if let Some(&(closest_line, distance)) = closest_pair {
+                    if distance < old_line.len() / 2 {
+                        return (old_line_number, closest_line);
+                    }
                 }
 
-                // Otherwise, we'll revert to the old line.
+                // Otherwise, we'll revert to the old line, or the last line if the
+                // section got shorter.
                 // If the old line now starts with a slash, search for one that doesn't.
                 if !old_line.starts_with('/') {
-                    return (old_line_number, old_line_number);
+                    return (old_line_number, old_line_number.min(last_line));
                 }
 
                 // Otherwise, go forward.
@@ -360,21 +740,55 @@ impl SectionMut<'_> {
                 }
 
                 // Otherwise, go backward.
-                let (prev_good_line, _) = new_lines
+                let prev_good_line = new_lines
                     .iter()
                     .enumerate()
                     .rev()
-                    .skip(new_lines.len() - old_line_number)
+                    .skip(new_lines.len().saturating_sub(old_line_number))
                     .find(|(_, &l)| !l.starts_with('/'))
-                    .unwrap();
+                    .map_or(old_line_number.min(last_line), |(i, _)| i);
                 (old_line_number, prev_good_line)
             })
             .collect();
 
         // Transform the thread's lines.
         for comment in &mut self.data_mut().comments {
-            comment.line = *line_map.get(&comment.line).unwrap();
+            if let Some(&line) = line_map.get(&comment.line) {
+                comment.line = line;
+            }
         }
+
+        // Move line reactions along with them, dropping those with no line to
+        // go to. A user's reactions to lines that merged count once.
+        let mut seen = HashSet::new();
+        self.data_mut().reactions.retain_mut(|r| {
+            if let Target::Line(line) = &mut r.target {
+                match line_map.get(line) {
+                    Some(&new_line) => *line = new_line,
+                    None => return false,
+                }
+            }
+            seen.insert((r.reaction, r.target, r.user.clone()))
+        });
+
+        // Record the change as a revision.
+        let mut revisions = self.revision_data();
+        if revisions.is_empty() && !old_content.is_empty() {
+            // Text saved before revisions were kept becomes the first revision.
+            revisions.push(RevisionData {
+                timestamp: self.date().and_hms_opt(0, 0, 0).unwrap().and_utc().timestamp(),
+                author: self.parent_entry().author_id().to_owned(),
+                diff: revision::diff("", &old_content),
+            });
+        }
+        revisions.push(RevisionData {
+            timestamp: Utc::now().timestamp(),
+            author,
+            diff: revision::diff(&old_content, &content),
+        });
+        self.revisions = Some(revisions);
+
+        self.content = Some(content);
     }
 
     /// Change the location of the section.
@@ -398,8 +812,13 @@ impl SectionMut<'_> {
         Ok(())
     }
 
-    /// Remove the section from the journal.
+    /// Remove the section from the journal, moving it to the trash.
     pub fn remove(mut self) {
+        let location = Location::Section {
+            entry: self.parent_entry_id().to_owned(),
+            index: self.index_in_parent(),
+        };
+
         // Update parent entry.
         let id = self.id;
         self.parent_entry_mut()
@@ -407,29 +826,65 @@ impl SectionMut<'_> {
             .sections
             .retain(|&s| s != id);
 
+        // Archive files.
+        let storage = self.index.storage.clone();
+        let mut trash_list = trash::load(self.index);
+        let mut trash = TrashData::new(id.to_string(), location);
+        let mut transaction = Transaction::new();
+        self.archive(&mut trash, &mut transaction);
+        trash_list.push(trash);
+        trash::stage(&trash_list, &mut transaction);
+        storage.commit(transaction).expect("error archiving section files");
+    }
+
+    /// Take the section out of the index and queue its files to be archived,
+    /// leaving its parent entry as it is.
+    pub(super) fn archive(mut self, trash: &mut TrashData, transaction: &mut Transaction) {
         // Update user reading history.
-        let user_ids: Vec<_> = self.index.users.keys().cloned().collect();
+        let id = self.id;
+        let user_ids: Vec<_> = self
+            .index
+            .users
+            .iter()
+            .filter(|(_, u)| u.history.iter().any(|h| h.section == id))
+            .map(|(id, _)| id.clone())
+            .collect();
         for user_id in user_ids {
             let mut user = self.index.user_mut(user_id).unwrap();
             user.data_mut().history.retain(|h| h.section != id);
         }
 
+        // Unlink the section from the sections that link to it.
+        let linking: Vec<_> = self
+            .index
+            .sections
+            .iter()
+            .filter(|(_, s)| s.perspectives.contains(&id))
+            .map(|(&id, _)| id)
+            .collect();
+        for section in linking {
+            let mut section = self.index.section_mut(section).unwrap();
+            section.data_mut().perspectives.retain(|&p| p != id);
+        }
+
         // Update index registry.
         self.index.sections.remove(&self.id);
 
-        // Archive files.
-        let now = Utc::now().timestamp();
-        fs::rename(
-            format!("content/sections/{}.json", &self.id),
-            format!("archived/section-{}-{now}", &self.id),
+        trash.archive(
+            transaction,
+            format!("content/sections/{id}.json"),
+            &format!("section-{id}"),
         );
-        fs::rename(
-            format!("content/sections/{}.txt", &self.id),
-            format!("archived/content-{}-{now}", &self.id),
+        trash.archive(
+            transaction,
+            format!("content/sections/{id}.txt"),
+            &format!("content-{id}"),
         );
-        fs::remove_file(format!("content/sections/{}.index", &self.id));
+        trash.archive(transaction, revision::path(id), &format!("revisions-{id}"));
+        transaction.remove(format!("content/sections/{id}.index"));
 
         // Prevent saving on drop.
+        self.index.dirty_sections.clear(&id);
         self.exists = false;
     }
 }
@@ -439,28 +894,47 @@ impl Drop for SectionMut<'_> {
         if !self.exists {
             return;
         }
+        let id = self.id;
+        let mut transaction = Transaction::new();
+
+        // Rebuild the search index only if the text it covers changed.
+        if self.index.dirty_sections.search.remove(&id) {
+            let heading = self.heading().cloned().unwrap_or_else(String::new);
+            let description = self.description().to_owned();
+            let summary = self.summary().to_owned();
+            let content = self.content();
+            let search_index = &mut self.data_mut().search_index;
+            *search_index = search::Index::new();
+            search_index.add_section("HEADING".to_owned(), &heading);
+            search_index.add_section("DESCRIPTION".to_owned(), &description);
+            search_index.add_section("SUMMARY".to_owned(), &summary);
+            search_index.add_section("CONTENT".to_owned(), &content);
+            let search_index_bytes = search_index.to_bytes();
+            self.data_mut().length = search_index.total_word_count();
+            transaction.write(format!("content/sections/{id}.index"), search_index_bytes);
+        }
 
-        // Create search index.
-        let id = self.id.clone();
-        let heading = self.heading().cloned().unwrap_or_else(String::new);
-        let description = self.description().to_owned();
-        let summary = self.summary().to_owned();
-        let content = self.content();
-        let search_index = &mut self.data_mut().search_index;
-        *search_index = search::Index::new();
-        search_index.add_section("HEADING".to_owned(), &heading);
-        search_index.add_section("DESCRIPTION".to_owned(), &description);
-        search_index.add_section("SUMMARY".to_owned(), &summary);
-        search_index.add_section("CONTENT".to_owned(), &content);
-        fs::write(
-            format!("content/sections/{id}.index"),
-            search_index.to_bytes(),
-        )
-        .unwrap();
-
-        // Write data.
-        self.data_mut().length = search_index.total_word_count();
-        let section = File::create(format!("content/sections/{id}.json")).unwrap();
-        serde_json::to_writer_pretty(section, self.data()).unwrap();
+        // Write whatever changed together.
+        if let Some(content) = self.content.take() {
+            transaction.write(format!("content/sections/{id}.txt"), content);
+        }
+        if let Some(revisions) = self.revisions.take() {
+            transaction.write(
+                revision::path(id),
+                serde_json::to_vec(&revisions).unwrap(),
+            );
+        }
+        if self.index.dirty_sections.data.remove(&id) {
+            transaction.write(
+                format!("content/sections/{id}.json"),
+                serde_json::to_vec_pretty(self.data()).unwrap(),
+            );
+        }
+        if !transaction.is_empty() {
+            self.index
+                .storage
+                .commit(transaction)
+                .expect("error writing section files");
+        }
     }
 }
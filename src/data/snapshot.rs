@@ -0,0 +1,192 @@
+use std::{
+    collections::HashSet,
+    fs::{self, File},
+    io::{self, Write},
+    path::Path,
+};
+
+use chrono::{DateTime, Datelike, NaiveDateTime, Utc};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use zip::{write::SimpleFileOptions, ZipArchive, ZipWriter};
+
+use super::storage::{Storage, DATA_DIRS};
+
+/// The format of snapshot file names, which record when they were taken.
+const NAME_FORMAT: &str = "edat-snapshot-%Y%m%d-%H%M%S.zip";
+
+/// How many snapshots to keep for each period.
+///
+/// The latest snapshot of each of the most recent days, weeks and months is
+/// kept, along with the latest snapshot overall. The rest are deleted.
+#[derive(Deserialize)]
+#[serde(default)]
+pub struct Retention {
+    /// How many days to keep a snapshot of.
+    pub daily: usize,
+
+    /// How many weeks to keep a snapshot of.
+    pub weekly: usize,
+
+    /// How many months to keep a snapshot of.
+    pub monthly: usize,
+}
+
+impl Default for Retention {
+    fn default() -> Self {
+        Retention {
+            daily: 7,
+            weekly: 4,
+            monthly: 12,
+        }
+    }
+}
+
+/// Identifies the period of time a snapshot was taken in.
+type Period = fn(&DateTime<Utc>) -> (i32, u32);
+
+/// A snapshot of the journal data in the snapshot directory.
+pub struct Snapshot {
+    /// The snapshot's file name.
+    pub name: String,
+
+    /// When the snapshot was taken.
+    pub taken: DateTime<Utc>,
+
+    /// The size of the snapshot file in bytes.
+    pub size: u64,
+}
+
+/// Write a compressed snapshot of the journal data to `dir`.
+///
+/// Snapshots are in the same format as the archives downloaded from the
+/// website, so they can be restored the same way. Nothing is written if the
+/// data hasn't changed since the latest snapshot, in which case that one is
+/// returned instead. Returns the snapshot and whether it is new.
+pub fn take(storage: &dyn Storage, dir: &Path) -> io::Result<(Snapshot, bool)> {
+    let mut files = Vec::new();
+    for data_dir in DATA_DIRS {
+        for path in storage.list(data_dir)? {
+            let contents = storage.read(&path)?;
+            files.push((path, contents));
+        }
+    }
+    let digest = digest(&files);
+
+    let snapshots = list(dir)?;
+    if let Some(latest) = snapshots.into_iter().next() {
+        if read_digest(&dir.join(&latest.name)).as_deref() == Some(digest.as_str()) {
+            return Ok((latest, false));
+        }
+    }
+
+    fs::create_dir_all(dir)?;
+    let taken = Utc::now();
+    let name = taken.format(NAME_FORMAT).to_string();
+    let partial = dir.join(format!("{name}.partial"));
+    let mut zip = ZipWriter::new(File::create(&partial)?);
+    let options = SimpleFileOptions::default();
+    for (path, contents) in files {
+        zip.start_file(path, options)?;
+        zip.write_all(&contents)?;
+    }
+    zip.set_comment(digest);
+    zip.finish()?;
+
+    // Only complete snapshots get a snapshot name.
+    let path = dir.join(&name);
+    fs::rename(partial, &path)?;
+    let size = fs::metadata(path)?.len();
+    Ok((Snapshot { name, taken, size }, true))
+}
+
+/// Every snapshot in `dir`, latest first.
+pub fn list(dir: &Path) -> io::Result<Vec<Snapshot>> {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e),
+    };
+
+    let mut snapshots = Vec::new();
+    for entry in entries {
+        let entry = entry?;
+        let Ok(name) = entry.file_name().into_string() else {
+            continue;
+        };
+        let Ok(taken) = NaiveDateTime::parse_from_str(&name, NAME_FORMAT) else {
+            continue;
+        };
+        snapshots.push(Snapshot {
+            name,
+            taken: taken.and_utc(),
+            size: entry.metadata()?.len(),
+        });
+    }
+    snapshots.sort_by_key(|s| std::cmp::Reverse(s.taken));
+    Ok(snapshots)
+}
+
+/// Delete the snapshots in `dir` that are no longer retained, returning how
+/// many were deleted.
+pub fn rotate(dir: &Path, retention: &Retention) -> io::Result<usize> {
+    let snapshots = list(dir)?;
+
+    let mut kept = HashSet::new();
+    kept.extend(snapshots.first().map(|s| s.name.clone()));
+    let periods: [(usize, Period); 3] = [
+        (retention.daily, |t| (t.year(), t.ordinal())),
+        (retention.weekly, |t| {
+            let week = t.iso_week();
+            (week.year(), week.week())
+        }),
+        (retention.monthly, |t| (t.year(), t.month())),
+    ];
+    for (count, period) in periods {
+        let mut seen = HashSet::new();
+        for snapshot in &snapshots {
+            if seen.len() == count {
+                break;
+            }
+            // Snapshots are latest first, so this is the latest of its period.
+            if seen.insert(period(&snapshot.taken)) {
+                kept.insert(snapshot.name.clone());
+            }
+        }
+    }
+
+    let mut deleted = 0;
+    for snapshot in snapshots {
+        if !kept.contains(&snapshot.name) {
+            fs::remove_file(dir.join(snapshot.name))?;
+            deleted += 1;
+        }
+    }
+    Ok(deleted)
+}
+
+/// A digest of the journal data, to tell whether it changed.
+///
+/// This is stored in snapshots, so it must not change between builds.
+fn digest(files: &[(String, Vec<u8>)]) -> String {
+    let mut hasher = Sha256::new();
+    for (path, content) in files {
+        // Lengths first, so the boundaries between files can't shift.
+        hasher.update((path.len() as u64).to_le_bytes());
+        hasher.update(path);
+        hasher.update((content.len() as u64).to_le_bytes());
+        hasher.update(content);
+    }
+    let hash: String = hasher
+        .finalize()
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect();
+    format!("edat-sha256 {hash}")
+}
+
+/// The digest recorded in a snapshot, if it can be read.
+fn read_digest(path: &Path) -> Option<String> {
+    let archive = ZipArchive::new(File::open(path).ok()?).ok()?;
+    String::from_utf8(archive.comment().to_vec()).ok()
+}
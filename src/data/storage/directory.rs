@@ -0,0 +1,229 @@
+use std::{
+    fs::{self, OpenOptions},
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+
+use super::*;
+
+/// Where a transaction records its pending moves until every one of them has
+/// been applied, relative to the storage root.
+const JOURNAL_PATH: &str = "content/transaction.journal";
+
+/// The extension given to a file while it is being staged.
+const STAGING_EXTENSION: &str = "staged";
+
+/// Journal data kept as plain files under a root directory.
+///
+/// Every written file is first staged next to its destination and flushed to
+/// disk. Once all of a transaction's files are staged, the list of moves is
+/// recorded in a journal, so that a crash partway through applying them can be
+/// finished when the directory is next opened.
+pub struct Directory {
+    root: PathBuf,
+}
+
+/// A file operation as recorded in the journal. Writes have already been
+/// staged, so only moves and deletions remain.
+#[derive(Serialize, Deserialize)]
+enum JournalOperation {
+    /// Move a file into place, replacing the destination.
+    Rename { from: String, to: String },
+
+    /// Delete a file.
+    Remove { path: String },
+}
+
+impl Directory {
+    /// Open the journal data under a root directory, finishing or discarding
+    /// any writes interrupted by a crash.
+    pub fn open(root: impl Into<PathBuf>) -> io::Result<Self> {
+        let directory = Directory { root: root.into() };
+        directory.recover()?;
+        Ok(directory)
+    }
+
+    fn path(&self, path: &str) -> PathBuf {
+        self.root.join(path)
+    }
+
+    fn recover(&self) -> io::Result<()> {
+        let journal_path = self.path(JOURNAL_PATH);
+        if journal_path.exists() {
+            // Every file was staged before the journal was written, so the
+            // transaction can be rolled forward.
+            let journal = fs::read(&journal_path)?;
+            match serde_json::from_slice::<Vec<JournalOperation>>(&journal) {
+                Ok(operations) => {
+                    println!(
+                        "Completing interrupted transaction of {} operations",
+                        operations.len()
+                    );
+                    for operation in &operations {
+                        self.apply(operation)?;
+                    }
+                }
+                Err(_) => println!("Discarding unreadable transaction journal"),
+            }
+            fs::remove_file(&journal_path)?;
+        }
+
+        // Staged files not covered by a journal belong to a transaction that
+        // never committed; the originals are still intact.
+        for dir in DATA_DIRS {
+            for path in self.list(dir)? {
+                if path.ends_with(&format!(".{STAGING_EXTENSION}")) {
+                    println!("Removing incomplete write {path}");
+                    fs::remove_file(self.path(&path))?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Apply a journaled operation.
+    ///
+    /// Operations are idempotent so that an interrupted transaction can be
+    /// replayed.
+    fn apply(&self, operation: &JournalOperation) -> io::Result<()> {
+        match operation {
+            JournalOperation::Rename { from, to } => {
+                let from = self.path(from);
+                if !from.exists() {
+                    // Already moved by an earlier, interrupted attempt.
+                    return Ok(());
+                }
+                let to = self.path(to);
+                if let Some(parent) = to.parent() {
+                    fs::create_dir_all(parent)?;
+                }
+                fs::rename(from, &to)?;
+                sync_parent(&to)
+            }
+            JournalOperation::Remove { path } => {
+                let path = self.path(path);
+                match fs::remove_file(&path) {
+                    Ok(()) => sync_parent(&path),
+                    Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(()),
+                    Err(e) => Err(e),
+                }
+            }
+        }
+    }
+}
+
+impl Storage for Directory {
+    fn read(&self, path: &str) -> io::Result<Vec<u8>> {
+        fs::read(self.path(path))
+    }
+
+    fn exists(&self, path: &str) -> bool {
+        self.path(path).is_file()
+    }
+
+    fn list(&self, dir: &str) -> io::Result<Vec<String>> {
+        fn walk(root: &Path, dir: &Path, files: &mut Vec<String>) -> io::Result<()> {
+            let entries = match fs::read_dir(dir) {
+                Ok(entries) => entries,
+                Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(()),
+                Err(e) => return Err(e),
+            };
+            for entry in entries {
+                let path = entry?.path();
+                if path.is_dir() {
+                    walk(root, &path, files)?;
+                } else {
+                    let relative = path.strip_prefix(root).unwrap();
+                    let relative: Vec<_> = relative
+                        .components()
+                        .map(|c| c.as_os_str().to_string_lossy())
+                        .collect();
+                    files.push(relative.join("/"));
+                }
+            }
+            Ok(())
+        }
+
+        let mut files = Vec::new();
+        walk(&self.root, &self.path(dir), &mut files)?;
+        files.sort();
+        Ok(files)
+    }
+
+    fn commit(&self, transaction: Transaction) -> io::Result<()> {
+        // Stage the new file contents.
+        let mut journal = Vec::new();
+        for operation in transaction.into_operations() {
+            journal.push(match operation {
+                Operation::Write { path, contents } => {
+                    let staged = format!("{path}.{STAGING_EXTENSION}");
+                    let staged_path = self.path(&staged);
+                    if let Some(parent) = staged_path.parent() {
+                        fs::create_dir_all(parent)?;
+                    }
+                    write_synced(&staged_path, &contents)?;
+                    JournalOperation::Rename {
+                        from: staged,
+                        to: path,
+                    }
+                }
+                Operation::Rename { from, to } => JournalOperation::Rename { from, to },
+                Operation::Remove { path } => JournalOperation::Remove { path },
+            });
+        }
+
+        match &journal[..] {
+            [] => return Ok(()),
+            // A lone write needs no journal: the rename is already atomic.
+            [operation] => return self.apply(operation),
+            _ => {}
+        }
+
+        // Record the operations so they can be replayed after a crash.
+        let journal_path = self.path(JOURNAL_PATH);
+        let staged_journal = self.path(&format!("{JOURNAL_PATH}.{STAGING_EXTENSION}"));
+        let journal_bytes =
+            serde_json::to_vec(&journal).expect("error serializing transaction journal");
+        fs::create_dir_all(journal_path.parent().unwrap())?;
+        write_synced(&staged_journal, &journal_bytes)?;
+        fs::rename(&staged_journal, &journal_path)?;
+        sync_parent(&journal_path)?;
+
+        for operation in &journal {
+            self.apply(operation)?;
+        }
+
+        fs::remove_file(&journal_path)?;
+        sync_parent(&journal_path)
+    }
+}
+
+fn write_synced(path: &Path, contents: &[u8]) -> io::Result<()> {
+    let mut file = OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .open(path)?;
+    file.write_all(contents)?;
+    file.sync_all()
+}
+
+/// Flush a directory entry change (creation, rename, removal) to disk.
+#[cfg(unix)]
+fn sync_parent(path: &Path) -> io::Result<()> {
+    let parent = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    fs::File::open(parent)?.sync_all()
+}
+
+/// Directories cannot be opened for syncing on this platform; renames are
+/// flushed by the filesystem.
+#[cfg(not(unix))]
+fn sync_parent(_path: &Path) -> io::Result<()> {
+    Ok(())
+}
@@ -0,0 +1,69 @@
+use std::{collections::BTreeMap, io, sync::Mutex};
+
+use super::*;
+
+/// Journal data held entirely in memory.
+///
+/// Nothing is ever written to disk, which makes this suitable for running
+/// against a throwaway fixture.
+#[derive(Default)]
+pub struct Memory {
+    files: Mutex<BTreeMap<String, Vec<u8>>>,
+}
+
+impl Memory {
+    /// Create an empty store.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+fn not_found(path: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::NotFound, format!("no file {path}"))
+}
+
+impl Storage for Memory {
+    fn read(&self, path: &str) -> io::Result<Vec<u8>> {
+        let files = self.files.lock().unwrap();
+        files.get(path).cloned().ok_or_else(|| not_found(path))
+    }
+
+    fn exists(&self, path: &str) -> bool {
+        self.files.lock().unwrap().contains_key(path)
+    }
+
+    fn list(&self, dir: &str) -> io::Result<Vec<String>> {
+        let prefix = format!("{dir}/");
+        let files = self.files.lock().unwrap();
+        Ok(files
+            .keys()
+            .filter(|p| p.starts_with(&prefix))
+            .cloned()
+            .collect())
+    }
+
+    fn commit(&self, transaction: Transaction) -> io::Result<()> {
+        let mut files = self.files.lock().unwrap();
+
+        // Apply to a copy so a failed operation leaves the store untouched.
+        let mut updated = files.clone();
+        for operation in transaction.into_operations() {
+            match operation {
+                Operation::Write { path, contents } => {
+                    updated.insert(path, contents);
+                }
+                Operation::Rename { from, to } => {
+                    if let Some(contents) = updated.remove(&from) {
+                        updated.insert(to, contents);
+                    }
+                }
+                Operation::Remove { path } => {
+                    updated.remove(&path);
+                }
+            }
+        }
+
+        *files = updated;
+        Ok(())
+    }
+}
@@ -0,0 +1,120 @@
+use std::io;
+
+pub use directory::Directory;
+pub use memory::Memory;
+pub use sqlite::Sqlite;
+
+mod directory;
+mod memory;
+mod sqlite;
+
+/// The top-level directories that hold journal data.
+pub const DATA_DIRS: [&str; 3] = ["content", "users", "archived"];
+
+/// A place journal data is loaded from and saved to.
+///
+/// Resources are addressed by slash-separated paths that mirror the original
+/// directory layout, such as `content/sections/12.txt`, whatever the backend
+/// actually does with them.
+pub trait Storage: Send + Sync {
+    /// Read the contents of a file.
+    fn read(&self, path: &str) -> io::Result<Vec<u8>>;
+
+    /// Whether a file exists.
+    fn exists(&self, path: &str) -> bool;
+
+    /// List the paths of every file under a directory, recursively.
+    fn list(&self, dir: &str) -> io::Result<Vec<String>>;
+
+    /// Apply every operation in a transaction, or none of them.
+    fn commit(&self, transaction: Transaction) -> io::Result<()>;
+
+    /// Read the contents of a text file.
+    fn read_to_string(&self, path: &str) -> io::Result<String> {
+        String::from_utf8(self.read(path)?)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    /// Replace the contents of a single file.
+    fn write(&self, path: &str, contents: Vec<u8>) -> io::Result<()> {
+        let mut transaction = Transaction::new();
+        transaction.write(path, contents);
+        self.commit(transaction)
+    }
+}
+
+/// A single file operation inside a transaction.
+pub(super) enum Operation {
+    /// Replace the contents of a file, creating it if needed.
+    Write { path: String, contents: Vec<u8> },
+
+    /// Move a file to a new location, replacing the destination. Nothing
+    /// happens if the file doesn't exist.
+    Rename { from: String, to: String },
+
+    /// Delete a file.
+    Remove { path: String },
+}
+
+/// A group of file updates that are committed together.
+#[derive(Default)]
+pub struct Transaction {
+    operations: Vec<Operation>,
+}
+
+impl Transaction {
+    /// Start an empty transaction.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Replace the contents of a file.
+    pub fn write(&mut self, path: impl Into<String>, contents: impl Into<Vec<u8>>) -> &mut Self {
+        self.operations.push(Operation::Write {
+            path: path.into(),
+            contents: contents.into(),
+        });
+        self
+    }
+
+    /// Move a file to a new location.
+    pub fn rename(&mut self, from: impl Into<String>, to: impl Into<String>) -> &mut Self {
+        self.operations.push(Operation::Rename {
+            from: from.into(),
+            to: to.into(),
+        });
+        self
+    }
+
+    /// Delete a file.
+    pub fn remove(&mut self, path: impl Into<String>) -> &mut Self {
+        self.operations.push(Operation::Remove { path: path.into() });
+        self
+    }
+
+    /// Whether the transaction has nothing to do.
+    pub fn is_empty(&self) -> bool {
+        self.operations.is_empty()
+    }
+
+    pub(super) fn into_operations(self) -> Vec<Operation> {
+        self.operations
+    }
+}
+
+/// Copy all journal data from one storage backend to another.
+///
+/// Returns the number of files copied.
+pub fn copy(from: &dyn Storage, to: &dyn Storage) -> io::Result<usize> {
+    let mut transaction = Transaction::new();
+    let mut count = 0;
+    for dir in DATA_DIRS {
+        for path in from.list(dir)? {
+            let contents = from.read(&path)?;
+            transaction.write(path, contents);
+            count += 1;
+        }
+    }
+    to.commit(transaction)?;
+    Ok(count)
+}
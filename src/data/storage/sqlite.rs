@@ -0,0 +1,110 @@
+use std::{io, path::Path, sync::Mutex};
+
+use rusqlite::{params, Connection, OptionalExtension};
+
+use super::*;
+
+/// Journal data kept in a single SQLite database file.
+///
+/// Each file of the directory layout is a row keyed by its path, and every
+/// transaction is a database transaction.
+pub struct Sqlite {
+    connection: Mutex<Connection>,
+}
+
+fn sql_error(e: rusqlite::Error) -> io::Error {
+    io::Error::other(e)
+}
+
+impl Sqlite {
+    /// Open the database at a path, creating it if it doesn't exist.
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        let connection = Connection::open(path).map_err(sql_error)?;
+        connection
+            .execute_batch(
+                "PRAGMA journal_mode = WAL;
+                PRAGMA synchronous = FULL;
+                CREATE TABLE IF NOT EXISTS files (
+                    path TEXT PRIMARY KEY NOT NULL,
+                    contents BLOB NOT NULL
+                );",
+            )
+            .map_err(sql_error)?;
+        Ok(Sqlite {
+            connection: Mutex::new(connection),
+        })
+    }
+}
+
+impl Storage for Sqlite {
+    fn read(&self, path: &str) -> io::Result<Vec<u8>> {
+        let connection = self.connection.lock().unwrap();
+        connection
+            .query_row(
+                "SELECT contents FROM files WHERE path = ?1",
+                params![path],
+                |row| row.get(0),
+            )
+            .optional()
+            .map_err(sql_error)?
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, format!("no file {path}")))
+    }
+
+    fn exists(&self, path: &str) -> bool {
+        let connection = self.connection.lock().unwrap();
+        connection
+            .query_row(
+                "SELECT 1 FROM files WHERE path = ?1",
+                params![path],
+                |_| Ok(()),
+            )
+            .optional()
+            .is_ok_and(|row| row.is_some())
+    }
+
+    fn list(&self, dir: &str) -> io::Result<Vec<String>> {
+        let connection = self.connection.lock().unwrap();
+        let mut statement = connection
+            .prepare("SELECT path FROM files WHERE substr(path, 1, length(?1)) = ?1 ORDER BY path")
+            .map_err(sql_error)?;
+        let paths = statement
+            .query_map(params![format!("{dir}/")], |row| row.get(0))
+            .map_err(sql_error)?
+            .collect::<Result<_, _>>()
+            .map_err(sql_error)?;
+        Ok(paths)
+    }
+
+    fn commit(&self, transaction: Transaction) -> io::Result<()> {
+        let mut connection = self.connection.lock().unwrap();
+        let tx = connection.transaction().map_err(sql_error)?;
+        for operation in transaction.into_operations() {
+            match operation {
+                Operation::Write { path, contents } => {
+                    tx.execute(
+                        "INSERT OR REPLACE INTO files (path, contents) VALUES (?1, ?2)",
+                        params![path, contents],
+                    )
+                    .map_err(sql_error)?;
+                }
+                Operation::Rename { from, to } => {
+                    tx.execute(
+                        "DELETE FROM files WHERE path = ?2 AND EXISTS (SELECT 1 FROM files WHERE path = ?1)",
+                        params![from, to],
+                    )
+                    .map_err(sql_error)?;
+                    tx.execute(
+                        "UPDATE files SET path = ?2 WHERE path = ?1",
+                        params![from, to],
+                    )
+                    .map_err(sql_error)?;
+                }
+                Operation::Remove { path } => {
+                    tx.execute("DELETE FROM files WHERE path = ?1", params![path])
+                        .map_err(sql_error)?;
+                }
+            }
+        }
+        tx.commit().map_err(sql_error)
+    }
+}
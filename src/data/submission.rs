@@ -0,0 +1,139 @@
+use serde::{Deserialize, Serialize};
+
+use super::{storage::Transaction, *};
+
+/// Where the list of guest submissions is kept.
+const SUBMISSIONS_PATH: &str = "users/submissions.json";
+
+/// An entry written by a member for a featured volume.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Submission {
+    /// A unique identifier for this submission.
+    pub id: u32,
+
+    /// The id of the member who wrote it.
+    pub author: String,
+
+    /// What the member has written.
+    pub draft: Draft,
+
+    /// Where the submission is in the review process.
+    pub status: Status,
+
+    /// The owner's reason for rejecting the submission or asking for changes.
+    pub note: String,
+
+    /// When the submission was started.
+    pub created: i64,
+
+    /// When the author last saved the submission.
+    pub updated: i64,
+
+    /// The id of the entry the submission was published as, once approved.
+    pub entry: Option<String>,
+}
+
+/// The text of a submission.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Draft {
+    /// The title of the entry.
+    pub title: String,
+
+    /// The description of the entry and its section.
+    pub description: String,
+
+    /// The summary of the entry and its section.
+    pub summary: String,
+
+    /// The text of the section.
+    pub content: String,
+}
+
+/// Where a submission is in the review process.
+#[derive(Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Debug)]
+pub enum Status {
+    /// The author is still writing it.
+    Draft,
+
+    /// The author has sent it to the owner for review.
+    Submitted,
+
+    /// The owner has sent it back to the author to be revised.
+    ChangesRequested,
+
+    /// The owner has turned it down.
+    Rejected,
+
+    /// The owner has published it.
+    Approved,
+}
+
+/// The statuses the owner can send a submission back to its author with.
+#[derive(Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Debug)]
+pub enum ReturnStatus {
+    /// The author should revise it and submit it again.
+    ChangesRequested,
+
+    /// The owner has turned it down.
+    Rejected,
+}
+
+impl From<ReturnStatus> for Status {
+    fn from(status: ReturnStatus) -> Self {
+        match status {
+            ReturnStatus::ChangesRequested => Status::ChangesRequested,
+            ReturnStatus::Rejected => Status::Rejected,
+        }
+    }
+}
+
+impl Submission {
+    /// Whether the author can still change the submission.
+    pub fn editable(&self) -> bool {
+        matches!(self.status, Status::Draft | Status::ChangesRequested)
+    }
+}
+
+/// Load the list of submissions, oldest first.
+pub(super) fn load(index: &Index) -> Vec<Submission> {
+    match index.storage.read(SUBMISSIONS_PATH) {
+        Ok(submissions) => {
+            serde_json::from_slice(&submissions).expect("submissions file invalid json")
+        }
+        Err(_) => Vec::new(),
+    }
+}
+
+/// Write the list of submissions.
+pub(super) fn save(index: &Index, submissions: &[Submission]) {
+    let mut transaction = Transaction::new();
+    stage(submissions, &mut transaction);
+    index
+        .storage
+        .commit(transaction)
+        .expect("error writing submissions file");
+}
+
+/// Queue the list of submissions to be written with other changes.
+pub(super) fn stage(submissions: &[Submission], transaction: &mut Transaction) {
+    let submissions =
+        serde_json::to_vec_pretty(submissions).expect("error serializing submissions file");
+    transaction.write(SUBMISSIONS_PATH, submissions);
+}
+
+/// Find a submission with the specified id that is awaiting review.
+pub(super) fn find_submitted(submissions: &[Submission], id: u32) -> DataResult<usize> {
+    let index = find(submissions, id)?;
+    match submissions[index].status {
+        Status::Submitted => Ok(index),
+        status => Err(DataError::NotSubmitted(id, status)),
+    }
+}
+
+/// Find a submission with the specified id.
+pub(super) fn find(submissions: &[Submission], id: u32) -> DataResult<usize> {
+    submissions
+        .iter()
+        .position(|s| s.id == id)
+        .ok_or_else(|| DataError::MissingResource("submission", id.to_string()))
+}
@@ -0,0 +1,136 @@
+use chrono::Utc;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use super::{storage::Transaction, *};
+
+/// Where the list of removed resources is kept.
+const TRASH_PATH: &str = "archived/trash.json";
+
+#[derive(Serialize, Deserialize)]
+pub(super) struct TrashData {
+    pub(super) id: String,
+    pub(super) location: Location,
+    pub(super) removed: i64,
+    pub(super) files: Vec<TrashFile>,
+}
+
+/// Where a removed resource used to be.
+#[derive(Serialize, Deserialize)]
+pub(super) enum Location {
+    Volume {
+        index: usize,
+    },
+    Entry {
+        volume: String,
+        part: usize,
+        index: usize,
+    },
+    Section {
+        entry: String,
+        index: usize,
+    },
+}
+
+/// A file moved to the archive along with a removed resource.
+#[derive(Serialize, Deserialize)]
+pub(super) struct TrashFile {
+    pub(super) original: String,
+    pub(super) archived: String,
+}
+
+/// A removed resource that can be restored.
+pub struct TrashItem {
+    /// The kind of resource: `volume`, `entry` or `section`.
+    pub kind: &'static str,
+
+    /// The resource id.
+    pub id: String,
+
+    /// The id of the resource it was removed from, if any.
+    pub parent: Option<String>,
+
+    /// When the resource was removed.
+    pub removed: i64,
+
+    /// The number of files archived with it, including those of its children.
+    pub files: usize,
+}
+
+impl TrashData {
+    pub(super) fn new(id: String, location: Location) -> Self {
+        TrashData {
+            id,
+            location,
+            removed: Utc::now().timestamp(),
+            files: Vec::new(),
+        }
+    }
+
+    /// Queue a file to be moved to the archive.
+    pub(super) fn archive(&mut self, transaction: &mut Transaction, original: String, name: &str) {
+        let archived = format!("archived/{name}-{}", self.removed);
+        transaction.rename(&original, &archived);
+        self.files.push(TrashFile { original, archived });
+    }
+
+    /// Queue the archived files to be moved back.
+    pub(super) fn unarchive(&self, transaction: &mut Transaction) {
+        for file in &self.files {
+            transaction.rename(&file.archived, &file.original);
+        }
+    }
+
+    pub(super) fn kind(&self) -> &'static str {
+        match self.location {
+            Location::Volume { .. } => "volume",
+            Location::Entry { .. } => "entry",
+            Location::Section { .. } => "section",
+        }
+    }
+
+    pub(super) fn item(&self) -> TrashItem {
+        let parent = match &self.location {
+            Location::Volume { .. } => None,
+            Location::Entry { volume, .. } => Some(volume.clone()),
+            Location::Section { entry, .. } => Some(entry.clone()),
+        };
+        TrashItem {
+            kind: self.kind(),
+            id: self.id.clone(),
+            parent,
+            removed: self.removed,
+            files: self.files.len(),
+        }
+    }
+}
+
+/// Load the list of removed resources, oldest first.
+pub(super) fn load(index: &Index) -> Vec<TrashData> {
+    match index.storage.read(TRASH_PATH) {
+        Ok(trash) => serde_json::from_slice(&trash).expect("trash file invalid json"),
+        Err(_) => Vec::new(),
+    }
+}
+
+/// Queue the list of removed resources to be written.
+pub(super) fn stage(trash: &[TrashData], transaction: &mut Transaction) {
+    let trash = serde_json::to_vec_pretty(trash).expect("error serializing trash file");
+    transaction.write(TRASH_PATH, trash);
+}
+
+/// Find the most recently removed resource of a kind with the specified id.
+pub(super) fn find(trash: &[TrashData], kind: &'static str, id: &str) -> DataResult<usize> {
+    trash
+        .iter()
+        .rposition(|t| t.kind() == kind && t.id == id)
+        .ok_or_else(|| DataError::MissingResource(kind, id.to_owned()))
+}
+
+/// Read a restored resource's data file.
+pub(super) fn read_data<T: DeserializeOwned>(index: &Index, path: &str) -> T {
+    let data = index
+        .storage
+        .read(path)
+        .unwrap_or_else(|_| panic!("error reading restored file {path}"));
+    serde_json::from_slice(&data).unwrap_or_else(|_| panic!("restored file {path} invalid json"))
+}
@@ -1,12 +1,13 @@
 use std::{
     collections::HashMap,
-    fs::{self, File}, hash::{Hash, Hasher},
+    hash::{Hash, Hasher},
 };
 
 use chrono::Utc;
+use rand::Rng;
 use serde::{Deserialize, Serialize};
 
-use super::*;
+use super::{storage::Transaction, *};
 
 #[derive(Serialize, Deserialize)]
 pub(super) struct UserData {
@@ -18,6 +19,16 @@ pub(super) struct UserData {
     pub(super) history: Vec<HistoryEntry>,
     pub(super) preferences: HashMap<String, String>,
     pub(super) init: bool,
+    #[serde(default)]
+    pub(super) feed_token: Option<String>,
+    #[serde(default)]
+    pub(super) followed_volumes: Vec<String>,
+    #[serde(default)]
+    pub(super) notifications: Vec<Notification>,
+    #[serde(default)]
+    pub(super) digest_token: Option<String>,
+    #[serde(default)]
+    pub(super) last_digest: Option<i64>,
 }
 
 /// The privilege level of a user.
@@ -81,6 +92,39 @@ macro_rules! immut_fns {
             &self.data().widgets
         }
 
+        /// The secret token in the user's feed link, if the feed is turned on.
+        pub fn feed_token(&self) -> Option<&String> {
+            self.data().feed_token.as_ref()
+        }
+
+        /// The ids of the volumes the user's feed is limited to.
+        ///
+        /// If this is empty, the feed covers every volume.
+        pub fn followed_volumes(&self) -> &[String] {
+            &self.data().followed_volumes
+        }
+
+        /// The user's notifications, oldest first.
+        pub fn notifications(&self) -> &[Notification] {
+            &self.data().notifications
+        }
+
+        /// The number of notifications the user hasn't seen.
+        pub fn unread_notifications(&self) -> usize {
+            self.data().notifications.iter().filter(|n| !n.read).count()
+        }
+
+        /// The secret token in the unsubscribe link of the user's digests, if
+        /// one has been sent.
+        pub fn digest_token(&self) -> Option<&String> {
+            self.data().digest_token.as_ref()
+        }
+
+        /// When the user's last digest covered up to, if they have had one.
+        pub fn last_digest(&self) -> Option<i64> {
+            self.data().last_digest
+        }
+
         /// The user's progress in a section.
         pub fn section_progress(&self, section: &Section) -> Option<SectionProgress> {
             self.data()
@@ -201,6 +245,7 @@ pub struct UserMut<'index> {
 
 impl UserMut<'_> {
     pub(super) fn data_mut(&mut self) -> &mut super::UserData {
+        self.index.dirty_users.insert(self.id.clone());
         self.index.users.get_mut(&self.id).unwrap()
     }
 
@@ -242,21 +287,47 @@ impl UserMut<'_> {
                 return Err(DataError::DuplicateId(new_id));
             }
 
-            // Update volume owners, entry authors, and section comments.
-            for volume in self.index.volumes.values_mut() {
+            // Update volume owners, entry authors, section comments, and mentions.
+            let index = &mut *self.index;
+            for (volume_id, volume) in &mut index.volumes {
                 if volume.owner == self.id {
                     volume.owner = new_id.clone();
+                    index.dirty_volumes.data.insert(volume_id.clone());
                 }
             }
-            for entry in self.index.entries.values_mut() {
+            for (entry_id, entry) in &mut index.entries {
                 if entry.author == self.id {
                     entry.author = new_id.clone();
+                    index.dirty_entries.data.insert(entry_id.clone());
                 }
             }
-            for section in self.index.sections.values_mut() {
+            for (&section_id, section) in &mut index.sections {
                 for comment in &mut section.comments {
                     if comment.author == self.id {
                         comment.author = new_id.clone();
+                        index.dirty_sections.data.insert(section_id);
+                    }
+                    for mentioned in &mut comment.mentioned {
+                        if *mentioned == self.id {
+                            *mentioned = new_id.clone();
+                            index.dirty_sections.data.insert(section_id);
+                        }
+                    }
+                }
+                for reaction in &mut section.reactions {
+                    if reaction.user == self.id {
+                        reaction.user = new_id.clone();
+                        index.dirty_sections.data.insert(section_id);
+                    }
+                }
+            }
+            for (user_id, user) in &mut index.users {
+                for notification in &mut user.notifications {
+                    if let Some(author) = notification.event.author_mut() {
+                        if *author == self.id {
+                            *author = new_id.clone();
+                            index.dirty_users.insert(user_id.clone());
+                        }
                     }
                 }
             }
@@ -265,11 +336,42 @@ impl UserMut<'_> {
             let data = self.index.users.remove(&self.id).unwrap();
             self.index.users.insert(new_id.clone(), data);
 
-            // Rename associated files.
-            let _ = fs::rename(
+            // Rename associated files, and update revision and submission
+            // authors with them.
+            let mut transaction = Transaction::new();
+            transaction.rename(
                 format!("users/{}.json", &self.id),
                 format!("users/{}.json", &new_id),
             );
+            for &section_id in self.index.sections.keys() {
+                let mut revisions = revision::load(self.index, section_id);
+                let mut changed = false;
+                for revision in &mut revisions {
+                    if revision.author == self.id {
+                        revision.author = new_id.clone();
+                        changed = true;
+                    }
+                }
+                if changed {
+                    transaction.write(
+                        revision::path(section_id),
+                        serde_json::to_vec(&revisions).unwrap(),
+                    );
+                }
+            }
+            let mut submissions = submission::load(self.index);
+            if submissions.iter().any(|s| s.author == self.id) {
+                for submission in &mut submissions {
+                    if submission.author == self.id {
+                        submission.author = new_id.clone();
+                    }
+                }
+                submission::stage(&submissions, &mut transaction);
+            }
+            self.index.storage.commit(transaction).expect("error renaming user file");
+
+            // Save the resources that refer to the user.
+            self.index.flush();
 
             self.id = new_id
         }
@@ -299,6 +401,47 @@ impl UserMut<'_> {
         self.data_mut().widgets = widgets;
     }
 
+    /// Give the user a new feed token, which stops the old feed link from
+    /// working, and return it.
+    pub fn reset_feed_token(&mut self) -> String {
+        let token = format!("{:032x}", rand::thread_rng().gen::<u128>());
+        self.data_mut().feed_token = Some(token.clone());
+        token
+    }
+
+    /// Turn off the user's feed.
+    pub fn revoke_feed_token(&mut self) {
+        self.data_mut().feed_token = None;
+    }
+
+    /// Limit the user's feed to the given volumes, or none for every volume.
+    pub fn set_followed_volumes(&mut self, volumes: Vec<String>) {
+        self.data_mut().followed_volumes = volumes;
+    }
+
+    /// Get the token for the user's unsubscribe link, making one if needed.
+    pub fn digest_token_or_new(&mut self) -> String {
+        self.data_mut()
+            .digest_token
+            .get_or_insert_with(|| format!("{:032x}", rand::thread_rng().gen::<u128>()))
+            .clone()
+    }
+
+    /// Record that the user's digest covers everything up to a time, or none
+    /// to start over.
+    pub fn set_last_digest(&mut self, timestamp: Option<i64>) {
+        self.data_mut().last_digest = timestamp;
+    }
+
+    /// Mark a notification as seen, or all of them if no id is given.
+    pub fn read_notifications(&mut self, id: Option<u32>) {
+        for notification in &mut self.data_mut().notifications {
+            if id.is_none_or(|id| id == notification.id) {
+                notification.read = true;
+            }
+        }
+    }
+
     /// Update the progress of the user in a section.
     ///
     /// Return [`false`] if the section doesn't exist.
@@ -400,9 +543,18 @@ impl UserMut<'_> {
 
 impl Drop for UserMut<'_> {
     fn drop(&mut self) {
+        if !self.index.dirty_users.remove(&self.id) {
+            return;
+        }
+
         // Write data.
-        let user = File::create(format!("users/{}.json", self.id)).unwrap();
-        serde_json::to_writer_pretty(user, self.data()).unwrap();
+        self.index
+            .storage
+            .write(
+                &format!("users/{}.json", self.id),
+                serde_json::to_vec_pretty(self.data()).unwrap(),
+            )
+            .expect("error writing user file");
     }
 }
 
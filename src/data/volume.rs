@@ -1,12 +1,9 @@
-use std::fs::{self, File};
-
-use chrono::Utc;
 use indexmap::IndexMap;
 use serde::{Deserialize, Serialize};
 
 use crate::search;
 
-use super::*;
+use super::{storage::Transaction, *};
 
 #[derive(Serialize, Deserialize)]
 pub struct VolumeData {
@@ -120,11 +117,6 @@ macro_rules! immut_fns {
             map
         }
 
-        /// Get the text content of the volume intro.
-        pub fn intro(&self) -> String {
-            fs::read_to_string(format!("content/volumes/{}.intro", self.id)).unwrap()
-        }
-
         /// Get the search index.
         pub fn search_index(&self) -> &search::Index {
             &self.data().search_index
@@ -134,6 +126,14 @@ macro_rules! immut_fns {
 
 impl Volume<'_> {
     immut_fns!();
+
+    /// Get the text content of the volume intro.
+    pub fn intro(&self) -> String {
+        self.index
+            .storage
+            .read_to_string(&format!("content/volumes/{}.intro", self.id))
+            .unwrap()
+    }
 }
 
 impl<'index> Volume<'index> {
@@ -161,13 +161,22 @@ pub struct VolumeMut<'index> {
     pub(super) index: &'index mut Index,
     pub(super) id: String,
     pub(super) exists: bool,
+
+    /// Intro text set since the last save, written out on drop.
+    pub(super) intro: Option<String>,
 }
 
 impl VolumeMut<'_> {
     pub(super) fn data_mut(&mut self) -> &mut VolumeData {
+        self.index.dirty_volumes.data.insert(self.id.clone());
         self.index.volumes.get_mut(&self.id).unwrap()
     }
 
+    /// Rebuild the search index when the wrapper is dropped.
+    pub fn reindex(&mut self) {
+        self.index.dirty_volumes.search.insert(self.id.clone());
+    }
+
     pub fn as_immut(&self) -> Volume {
         Volume {
             index: &self.index,
@@ -189,6 +198,18 @@ impl VolumeMut<'_> {
 
     immut_fns!();
 
+    /// Get the text content of the volume intro, including unsaved changes.
+    pub fn intro(&self) -> String {
+        match &self.intro {
+            Some(intro) => intro.clone(),
+            None => self
+                .index
+                .storage
+                .read_to_string(&format!("content/volumes/{}.intro", self.id))
+                .unwrap(),
+        }
+    }
+
     /// Set the volume title.
     pub fn set_title(&mut self, title: &str) -> DataResult<()> {
         let title = process_text(title);
@@ -207,18 +228,21 @@ impl VolumeMut<'_> {
             self.data_mut().old_ids.push(old_id);
 
             // Rename associated files.
-            let _ = fs::rename(
-                format!("content/volumes/{}.json", &self.id),
-                format!("content/volumes/{}.json", &new_id),
-            );
-            let _ = fs::rename(
-                format!("content/volumes/{}.index", &self.id),
-                format!("content/volumes/{}.index", &new_id),
-            );
-            let _ = fs::rename(
-                format!("content/volumes/{}.intro", &self.id),
-                format!("content/volumes/{}.intro", &new_id),
-            );
+            let mut transaction = Transaction::new();
+            transaction
+                .rename(
+                    format!("content/volumes/{}.json", &self.id),
+                    format!("content/volumes/{}.json", &new_id),
+                )
+                .rename(
+                    format!("content/volumes/{}.index", &self.id),
+                    format!("content/volumes/{}.index", &new_id),
+                )
+                .rename(
+                    format!("content/volumes/{}.intro", &self.id),
+                    format!("content/volumes/{}.intro", &new_id),
+                );
+            self.index.storage.commit(transaction).expect("error renaming volume files");
 
             // Update child entries.
             let entries = self.entry_ids().to_owned();
@@ -231,6 +255,23 @@ impl VolumeMut<'_> {
                     .0 = new_id.clone();
             }
 
+            // Update users following the volume.
+            let followers: Vec<_> = self
+                .index
+                .users
+                .iter()
+                .filter(|(_, u)| u.followed_volumes.contains(&self.id))
+                .map(|(id, _)| id.clone())
+                .collect();
+            for user in followers {
+                let mut user = self.index.user_mut(user).unwrap();
+                for followed in &mut user.data_mut().followed_volumes {
+                    if *followed == self.id {
+                        *followed = new_id.clone();
+                    }
+                }
+            }
+
             // Update index registry.
             let volume_index = self.index_in_list();
             let data = self
@@ -247,12 +288,14 @@ impl VolumeMut<'_> {
         }
 
         self.data_mut().title = title;
+        self.reindex();
         Ok(())
     }
 
     /// Set the volume subtitle.
     pub fn set_subtitle(&mut self, subtitle: Option<&str>) {
         self.data_mut().subtitle = subtitle.map(|s| process_text(s));
+        self.reindex();
     }
 
     /// Set the volume content kind.
@@ -262,9 +305,9 @@ impl VolumeMut<'_> {
 
     /// Set the volume intro text.
     pub fn set_intro(&mut self, intro: &str) {
-        // Format and write the content.
-        let content = process_text(&intro);
-        fs::write(format!("content/volumes/{}.intro", self.id), content);
+        // Format the content. It is written alongside the volume data on drop.
+        self.intro = Some(process_text(intro));
+        self.reindex();
     }
 
     /// Change the location of the volume.
@@ -282,29 +325,60 @@ impl VolumeMut<'_> {
         Ok(())
     }
 
-    /// Remove the volume from the journal.
+    /// Remove the volume and everything in it from the journal, moving them
+    /// to the trash.
     pub fn remove(mut self) {
-        // Orphan this volume's entries.
+        let location = Location::Volume {
+            index: self.index_in_list(),
+        };
+
+        let storage = self.index.storage.clone();
+        let mut trash_list = trash::load(self.index);
+        let mut trash = TrashData::new(self.id.clone(), location);
+        let mut transaction = Transaction::new();
+
+        // Take out this volume's entries.
         let entry_ids = self.entry_ids().to_owned();
         for id in entry_ids {
-            self.index.entry_mut(id).unwrap().remove();
+            self.index.entry_mut(id).unwrap().archive(&mut trash, &mut transaction);
         }
 
-        // Update index registry.
+        // Update index registry, in the same transaction as the archive.
         self.index.volumes.shift_remove(&self.id);
-        self.index.save();
+        self.index.stage_index(&mut transaction);
 
         // Archive files.
-        let now = Utc::now().timestamp();
-        let _ = fs::rename(
+        trash.archive(
+            &mut transaction,
             format!("content/volumes/{}.json", &self.id),
-            format!("archive/volume-{}-{now}", &self.id),
+            &format!("volume-{}", &self.id),
         );
-        let _ = fs::rename(
+        trash.archive(
+            &mut transaction,
             format!("content/volumes/{}.intro", &self.id),
-            format!("archive/intro-{}-{now}", &self.id),
+            &format!("intro-{}", &self.id),
         );
+        transaction.remove(format!("content/volumes/{}.index", &self.id));
+        trash_list.push(trash);
+        trash::stage(&trash_list, &mut transaction);
+        storage.commit(transaction).expect("error archiving volume files");
+
+        // Stop users following the volume, so those who only followed this
+        // one go back to following every volume.
+        let followers: Vec<_> = self
+            .index
+            .users
+            .iter()
+            .filter(|(_, u)| u.followed_volumes.contains(&self.id))
+            .map(|(id, _)| id.clone())
+            .collect();
+        for user in followers {
+            let mut user = self.index.user_mut(user).unwrap();
+            user.data_mut().followed_volumes.retain(|v| *v != self.id);
+        }
 
+        // Prevent saving on drop.
+        self.index.dirty_volumes.clear(&self.id);
         self.exists = false;
     }
 }
@@ -314,25 +388,40 @@ impl Drop for VolumeMut<'_> {
         if !self.exists {
             return;
         }
-
-        // Create search index.
         let id = self.id.clone();
-        let title = self.title().to_owned();
-        let subtitle = self.subtitle().map(|s| s.clone()).unwrap_or_else(String::new);
-        let intro = self.intro();
-        let search_index = &mut self.data_mut().search_index;
-        *search_index = search::Index::new();
-        search_index.add_section("TITLE".to_owned(), &title);
-        search_index.add_section("SUBTITLE".to_owned(), &subtitle);
-        search_index.add_section("INTRO".to_owned(), &intro);
-        fs::write(
-            format!("content/volumes/{id}.index"),
-            search_index.to_bytes(),
-        )
-        .unwrap();
-
-        // Write data.
-        let volume = File::create(format!("content/volumes/{id}.json")).unwrap();
-        serde_json::to_writer_pretty(volume, self.data()).unwrap();
+        let mut transaction = Transaction::new();
+
+        // Rebuild the search index only if the text it covers changed.
+        if self.index.dirty_volumes.search.remove(&id) {
+            let title = self.title().to_owned();
+            let subtitle = self.subtitle().cloned().unwrap_or_default();
+            let intro = self.intro();
+            let search_index = &mut self.index.volumes.get_mut(&id).unwrap().search_index;
+            *search_index = search::Index::new();
+            search_index.add_section("TITLE".to_owned(), &title);
+            search_index.add_section("SUBTITLE".to_owned(), &subtitle);
+            search_index.add_section("INTRO".to_owned(), &intro);
+            transaction.write(
+                format!("content/volumes/{id}.index"),
+                search_index.to_bytes(),
+            );
+        }
+
+        // Write whatever changed together.
+        if let Some(intro) = self.intro.take() {
+            transaction.write(format!("content/volumes/{id}.intro"), intro);
+        }
+        if self.index.dirty_volumes.data.remove(&id) {
+            transaction.write(
+                format!("content/volumes/{id}.json"),
+                serde_json::to_vec_pretty(self.data()).unwrap(),
+            );
+        }
+        if !transaction.is_empty() {
+            self.index
+                .storage
+                .commit(transaction)
+                .expect("error writing volume files");
+        }
     }
 }
@@ -0,0 +1,203 @@
+use chrono::DateTime;
+use lettre::{
+    message::{header::ContentType, Mailbox},
+    transport::smtp::authentication::Credentials,
+    AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor,
+};
+use serde::Deserialize;
+
+use crate::{
+    data::{strip_formatting, Event, User},
+    feed::followed_sections,
+};
+
+/// How many sections a digest lists.
+const DIGEST_LENGTH: usize = 20;
+
+/// The mail server digests are sent through.
+#[derive(Deserialize)]
+#[serde(default)]
+pub struct Smtp {
+    /// The host name of the server.
+    pub host: String,
+
+    /// The port to connect on.
+    pub port: u16,
+
+    /// Whether to upgrade the connection with STARTTLS. Turn this off for a
+    /// local relay or test sink.
+    pub starttls: bool,
+
+    /// The user name to log in with, if the server needs one.
+    pub username: Option<String>,
+
+    /// The password to log in with.
+    pub password: Option<String>,
+
+    /// The address digests are sent from, like `Journal <journal@example.com>`.
+    pub from: String,
+}
+
+impl Default for Smtp {
+    fn default() -> Self {
+        Smtp {
+            host: "localhost".to_owned(),
+            port: 587,
+            starttls: true,
+            username: None,
+            password: None,
+            from: "edat@localhost".to_owned(),
+        }
+    }
+}
+
+/// How often a user wants a digest, read from their `digest` preference.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Frequency {
+    Daily,
+    Weekly,
+}
+
+impl Frequency {
+    /// The user's chosen frequency, if they want digests at all.
+    pub fn of(user: &User) -> Option<Self> {
+        match user.preferences().get("digest")?.as_str() {
+            "daily" => Some(Frequency::Daily),
+            "weekly" => Some(Frequency::Weekly),
+            _ => None,
+        }
+    }
+
+    /// The time between digests, in seconds.
+    pub fn period(self) -> i64 {
+        match self {
+            Frequency::Daily => 24 * 60 * 60,
+            Frequency::Weekly => 7 * 24 * 60 * 60,
+        }
+    }
+}
+
+/// The address a user wants digests sent to, if they gave a valid one.
+pub fn address(user: &User) -> Option<Mailbox> {
+    let email = user.preferences().get("email")?;
+    let address = email.trim().parse().ok()?;
+    Some(Mailbox::new(Some(user.full_name()), address))
+}
+
+/// Write a digest of what happened since `since` for a user, in plain text.
+///
+/// Lists the sections marked complete in the volumes the user follows and the
+/// comments left on the user's writing or in their threads. Returns [`None`]
+/// if there is nothing to tell. `base` is the address of the website and
+/// `token` is the user's digest token, for the unsubscribe link.
+pub fn compose(user: &User, since: i64, base: &str, token: &str) -> Option<String> {
+    let since = DateTime::from_timestamp(since, 0).unwrap_or(DateTime::UNIX_EPOCH);
+    let index = user.index();
+    let first_name = |id: &str| {
+        index
+            .user(id.to_owned())
+            .map_or_else(|_| "Someone".to_owned(), |u| u.first_name().to_owned())
+    };
+
+    let mut sections = followed_sections(user);
+    sections.retain(|(completed, _)| *completed > since);
+    sections.truncate(DIGEST_LENGTH);
+
+    let comments: Vec<_> = user
+        .notifications()
+        .iter()
+        .filter(|n| n.timestamp > since.timestamp())
+        .filter_map(|n| {
+            let (section, line, author, text) = match &n.event {
+                Event::Comment {
+                    section,
+                    line,
+                    author,
+                } => (section, line, author, "commented on"),
+                Event::Reply {
+                    section,
+                    line,
+                    author,
+                } => (section, line, author, "replied to your comment on"),
+                Event::Mention {
+                    section,
+                    line,
+                    author,
+                } => (section, line, author, "mentioned you on"),
+                _ => return None,
+            };
+            let section = index.section(*section).ok()?;
+            Some(format!(
+                "{} {text} {}\n{base}/section/{}?line={line}\n",
+                first_name(author),
+                strip_formatting(section.parent_entry().title()),
+                section.id(),
+            ))
+        })
+        .collect();
+
+    if sections.is_empty() && comments.is_empty() {
+        return None;
+    }
+
+    let mut digest = format!("Hi {},\n\n", user.first_name());
+    if !sections.is_empty() {
+        digest += "New on Every Day’s a Thursday:\n\n";
+        for (_, section) in sections.iter().rev() {
+            let entry = section.parent_entry();
+            let mut title = strip_formatting(entry.title());
+            if let Some(heading) = section.heading() {
+                title += &format!(": {}", strip_formatting(heading));
+            }
+            digest += &format!(
+                "{title}\n{}\n{base}/section/{}\n\n",
+                strip_formatting(section.description()),
+                section.id()
+            );
+        }
+    }
+    if !comments.is_empty() {
+        digest += "Comments:\n\n";
+        for comment in comments {
+            digest += &comment;
+            digest += "\n";
+        }
+    }
+    digest += &format!(
+        "--\nYou asked for this digest in your profile settings. To stop getting it, go to {base}/unsubscribe/{token}.\n"
+    );
+    Some(digest)
+}
+
+/// Send a digest through the mail server.
+pub async fn send(smtp: &Smtp, to: Mailbox, body: String) -> Result<(), String> {
+    let from = smtp
+        .from
+        .parse()
+        .map_err(|e| format!("invalid sender: {e}"))?;
+    let message = Message::builder()
+        .from(from)
+        .to(to)
+        .subject("Your Every Day’s a Thursday digest")
+        .header(ContentType::TEXT_PLAIN)
+        .body(body)
+        .map_err(|e| e.to_string())?;
+
+    let mut transport = if smtp.starttls {
+        AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&smtp.host)
+            .map_err(|e| e.to_string())?
+    } else {
+        AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(&smtp.host)
+    }
+    .port(smtp.port);
+    if let (Some(username), Some(password)) = (&smtp.username, &smtp.password) {
+        transport = transport.credentials(Credentials::new(username.clone(), password.clone()));
+    }
+
+    transport
+        .build()
+        .send(message)
+        .await
+        .map(|_| ())
+        .map_err(|e| e.to_string())
+}
@@ -0,0 +1,108 @@
+use chrono::{DateTime, Utc};
+
+use crate::{
+    book::{escape, long_date},
+    data::{section::Status, strip_formatting, Section, User},
+};
+
+/// How many sections a feed lists.
+const FEED_LENGTH: usize = 50;
+
+/// Render a user's feed of the sections most recently marked complete as an
+/// Atom document.
+///
+/// The feed only covers the volumes the user follows. `base` is the address
+/// of the website, which feed readers need to follow the links.
+pub fn atom(user: &User, base: &str) -> String {
+    let mut sections = followed_sections(user);
+    sections.truncate(FEED_LENGTH);
+
+    let updated = sections.first().map_or_else(Utc::now, |(c, _)| *c);
+    let mut feed = format!(
+        "<?xml version=\"1.0\" encoding=\"utf-8\"?>
+<feed xmlns=\"http://www.w3.org/2005/Atom\">
+<title>Every Day’s a Thursday</title>
+<subtitle>New sections for {}</subtitle>
+<id>urn:edat:feed:{}</id>
+<link rel=\"alternate\" href=\"{base}/\"/>
+<updated>{}</updated>
+",
+        escape(&user.full_name()),
+        user.id(),
+        timestamp(&updated)
+    );
+    for (completed, section) in &sections {
+        feed += &entry(section, completed, base);
+    }
+    feed += "</feed>\n";
+    feed
+}
+
+/// The complete sections in the volumes the user follows, or every volume if
+/// they don't follow any, with when they were marked complete, newest first.
+pub fn followed_sections<'a>(user: &'a User) -> Vec<(DateTime<Utc>, Section<'a>)> {
+    let followed = user.followed_volumes();
+    let mut sections: Vec<_> = user
+        .index()
+        .sections()
+        .filter(|s| s.status() == Status::Complete)
+        .filter_map(|s| Some((s.completed()?, s)))
+        .filter(|(_, s)| {
+            followed.is_empty()
+                || followed
+                    .iter()
+                    .any(|v| v == s.parent_entry().parent_volume_id())
+        })
+        .collect();
+    sections.sort_by_key(|(completed, _)| std::cmp::Reverse(*completed));
+    sections
+}
+
+fn entry(section: &Section, completed: &DateTime<Utc>, base: &str) -> String {
+    let entry = section.parent_entry();
+    let volume = entry.parent_volume();
+
+    let mut title = entry.title().to_owned();
+    if let Some(heading) = section.heading() {
+        title += &format!(": {heading}");
+    }
+    let mut context = format!("From <i>{}</i> in <i>{}</i>", entry.title(), volume.title());
+    if volume.parts_count() > 1 {
+        context += &format!(
+            ", volume {}",
+            roman::to(1 + entry.parent_volume_part() as i32).unwrap()
+        );
+    }
+    context += &format!(". Added {}.", long_date(&section.date()));
+    let mut content = String::new();
+    if !section.summary().is_empty() {
+        content += &format!("<p>{}</p>", section.summary());
+    }
+    content += &format!("<p>{context}</p>");
+
+    let link = format!("{base}/section/{}", section.id());
+    format!(
+        "<entry>
+<title>{}</title>
+<id>{link}</id>
+<link rel=\"alternate\" href=\"{link}\"/>
+<updated>{}</updated>
+<author><name>{}</name></author>
+<category term=\"{}\" label=\"{}\"/>
+<summary>{}</summary>
+<content type=\"html\">{}</content>
+</entry>
+",
+        escape(&strip_formatting(&title)),
+        timestamp(completed),
+        escape(&entry.author().full_name()),
+        volume.id(),
+        escape(&strip_formatting(volume.title())),
+        escape(&strip_formatting(section.description())),
+        escape(&content)
+    )
+}
+
+fn timestamp(time: &DateTime<Utc>) -> String {
+    time.format("%Y-%m-%dT%H:%M:%SZ").to_string()
+}
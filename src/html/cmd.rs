@@ -43,6 +43,7 @@ pub struct EntryInfo {
     pub parent_volume: (String, usize),
     pub author: String,
     pub sections: Vec<EntrySection>,
+    pub tags: String,
 }
 
 pub struct EntrySection {
@@ -61,6 +62,8 @@ pub struct SectionInfo {
     pub in_entry: (usize, usize),
     pub length: usize,
     pub perspectives: String,
+    pub tags: String,
+    pub publish_at: Option<i64>,
     pub comments: Vec<SectionComment>,
 }
 
@@ -70,8 +73,30 @@ pub struct SectionComment {
     pub contents: String,
 }
 
+pub struct RevisionsInfo {
+    pub id: u32,
+    pub revisions: Vec<SectionRevision>,
+}
+
+pub struct SectionRevision {
+    pub number: usize,
+    pub author: String,
+    pub timestamp: i64,
+    pub added: usize,
+    pub removed: usize,
+}
+
 pub struct Volumes(pub Vec<(String, String)>);
 
+pub struct TrashEntry {
+    pub kind: &'static str,
+    pub id: String,
+    pub parent: String,
+    pub removed: i64,
+}
+
+pub struct Trash(pub Vec<TrashEntry>);
+
 pub fn ok() -> maud::Markup {
     html! {
         p.ok { "Done" }
@@ -96,6 +121,12 @@ pub fn bad_date(date: &str) -> maud::Markup {
     }
 }
 
+pub fn no_retention() -> maud::Markup {
+    html! {
+        p.error { "No trash retention period set" }
+    }
+}
+
 pub fn unauthorized() -> maud::Markup {
     html! {
         p.error { "Not authorized" }
@@ -235,6 +266,244 @@ pub fn volumes(volumes: Volumes) -> maud::Markup {
     }
 }
 
+pub fn tags(tags: Vec<(String, usize)>) -> maud::Markup {
+    html! {
+        p { "Tags:" }
+        @if tags.is_empty() {
+            p { "No tags in use" }
+        }
+        ul {
+            @for (tag, count) in tags {
+                li {
+                    mono { (tag) }
+                    " — "
+                    (count) " tagged"
+                }
+            }
+        }
+    }
+}
+
+pub fn trash(trash: Trash) -> maud::Markup {
+    html! {
+        p { "Trash:" }
+        ul {
+            @for item in trash.0 {
+                li {
+                    (item.kind)
+                    " "
+                    mono { (item.id) }
+                    @if !item.parent.is_empty() {
+                        " from "
+                        mono { (item.parent) }
+                    }
+                    " removed "
+                    utc { (item.removed) }
+                }
+            }
+        }
+    }
+}
+
+pub fn purged(count: usize) -> maud::Markup {
+    html! {
+        p.ok { "Purged " (count) " from the trash" }
+    }
+}
+
+pub fn archive_error(id: &str) -> maud::Markup {
+    html! {
+        p.error { "Failed to upload archive: " (id) }
+    }
+}
+
+pub fn archive_success(id: &str) -> maud::Markup {
+    html! {
+        p { "Archive uploaded: " (id) }
+    }
+}
+
+pub fn archives(archives: Vec<String>) -> maud::Markup {
+    html! {
+        p { b { "Archives console" } }
+        @if archives.is_empty() {
+            p { "No archives saved" }
+        }
+        ul {
+            @for archive in archives {
+                li { mono { (archive) } }
+            }
+        }
+        p { "Upload a downloaded archive here" }
+        input #archive-upload type="file" accept=".zip";
+        button #upload-archive { "Upload" }
+        #archive-feedback {}
+        p { "Enter " mono { "restore <archive> [replace|merge]" } " to preview a restore" }
+    }
+}
+
+pub fn restore_error(archive: &str, error: std::io::Error) -> maud::Markup {
+    html! {
+        p.error { "Can't restore " mono { (archive) } ": " (error) }
+    }
+}
+
+pub fn restore_preview(
+    archive: &str,
+    mode: restore::Mode,
+    problems: &[check::Problem],
+    changes: restore::Changes,
+) -> maud::Markup {
+    let mode = format!("{mode:?}").to_lowercase();
+    html! {
+        p { b { "Restoring " mono { (archive) } " (" (mode) ")" } }
+        @if !problems.is_empty() {
+            p.error { "The backup has problems and can't be restored:" }
+            ul {
+                @for problem in problems {
+                    li { (problem) }
+                }
+            }
+        }
+        @if changes.is_empty() {
+            p { "No changes" }
+        }
+        @for (heading, resources) in [
+            ("Added", &changes.added),
+            ("Changed", &changes.changed),
+            ("Removed", &changes.removed),
+        ] {
+            @if !resources.is_empty() {
+                p { (heading) ":" }
+                ul {
+                    @for resource in resources {
+                        li { (resource) }
+                    }
+                }
+            }
+        }
+        @if problems.is_empty() && !changes.is_empty() {
+            p { "Enter " mono { "restore " (archive) " " (mode) " confirm" } " to restore" }
+        }
+    }
+}
+
+pub fn restored(archive: &str, changes: restore::Changes) -> maud::Markup {
+    html! {
+        p.ok {
+            "Restored " mono { (archive) } ": "
+            (changes.added.len()) " added, "
+            (changes.changed.len()) " changed, "
+            (changes.removed.len()) " removed"
+        }
+    }
+}
+
+pub fn snapshots(snapshots: Vec<snapshot::Snapshot>) -> maud::Markup {
+    html! {
+        p { "Snapshots:" }
+        @if snapshots.is_empty() {
+            p { "No snapshots taken" }
+        }
+        ul {
+            @for snapshot in snapshots {
+                li {
+                    mono { (snapshot.name) }
+                    " taken "
+                    utc { (snapshot.taken.timestamp()) }
+                    " — "
+                    (snapshot.size.div_ceil(1024)) " KB"
+                }
+            }
+        }
+    }
+}
+
+pub fn snapshot_taken(snapshot: snapshot::Snapshot, new: bool) -> maud::Markup {
+    html! {
+        @if new {
+            p.ok { "Took snapshot " mono { (snapshot.name) } }
+        } @else {
+            p.ok { "No changes since snapshot " mono { (snapshot.name) } }
+        }
+    }
+}
+
+pub fn snapshot_error(error: std::io::Error) -> maud::Markup {
+    html! {
+        p.error { "Snapshot failed: " (error) }
+    }
+}
+
+pub fn submissions(submissions: Vec<Submission>) -> maud::Markup {
+    html! {
+        p { "Submissions:" }
+        @if submissions.is_empty() {
+            p { "Nothing submitted" }
+        }
+        ul {
+            @for submission in submissions {
+                li {
+                    mono { (submission.id) }
+                    " "
+                    (PreEscaped(&submission.draft.title))
+                    " by "
+                    mono { (submission.author) }
+                    " — "
+                    (format!("{:?}", submission.status))
+                    " "
+                    utc { (submission.updated) }
+                }
+            }
+        }
+    }
+}
+
+pub fn submission(submission: Submission) -> maud::Markup {
+    html! {
+        p { b { "Submission " mono { (submission.id) } } }
+        p { "Author: " mono.info { (submission.author) } }
+        p { "Status: " mono.info { (format!("{:?}", submission.status)) } }
+        p { "Started: " utc.info { (submission.created) } }
+        p { "Updated: " utc.info { (submission.updated) } }
+        @if let Some(entry) = &submission.entry {
+            p { "Entry: " mono.info { (entry) } }
+        }
+        @if !submission.note.is_empty() {
+            p { "Note: " span.info { (PreEscaped(&submission.note)) } }
+        }
+        p { "Title: " span.info { (PreEscaped(&submission.draft.title)) } }
+        p { "Description: " span.info { (PreEscaped(&submission.draft.description)) } }
+        p { "Summary: " span.info { (PreEscaped(&submission.draft.summary)) } }
+        blockquote {
+            @for line in submission.draft.content.lines() {
+                p { (PreEscaped(line)) }
+            }
+        }
+    }
+}
+
+pub fn not_submitted(id: u32, status: submission::Status) -> maud::Markup {
+    html! {
+        p.error {
+            "Submission " mono { (id) } " is "
+            (format!("{status:?}")) ", not awaiting review"
+        }
+    }
+}
+
+pub fn already_complete(section: u32) -> maud::Markup {
+    html! {
+        p.error { "Section " mono { (section) } " is already complete" }
+    }
+}
+
+pub fn not_featured(volume: &str) -> maud::Markup {
+    html! {
+        p.error { "Volume " mono { (volume) } " is not for featured content" }
+    }
+}
+
 pub fn entry(entry: EntryInfo) -> maud::Markup {
     html! {
         p { b { "Entry " mono { (entry.id) } } }
@@ -243,6 +512,7 @@ pub fn entry(entry: EntryInfo) -> maud::Markup {
             span.info { mono { (entry.parent_volume.0) } " " (entry.parent_volume.1) }
         }
         p { "Author: " mono.info { (entry.author) } }
+        p { "Tags: " mono.info { (entry.tags) } }
         p { "Sections:" }
         ul {
             @for section in &entry.sections {
@@ -290,9 +560,13 @@ pub fn section(section: SectionInfo) -> maud::Markup {
             }
         }
         p { "Status: " mono.info { (section.status) } }
+        @if let Some(publish_at) = section.publish_at {
+            p { "Scheduled: " utc.info.time { (publish_at) } }
+        }
         p { "Length: " span.info { (section.length) } }
         (edit_section(Some(&section), &section.date))
         p { "Perspectives: " mono.info { (section.perspectives) }}
+        p { "Tags: " mono.info { (section.tags) } }
         p { "Comments: " }
         ul {
             @for comment in &section.comments {
@@ -331,6 +605,46 @@ pub fn edit_section(section: Option<&SectionInfo>, date: &str) -> maud::Markup {
     }
 }
 
+pub fn revisions(revisions: RevisionsInfo) -> maud::Markup {
+    html! {
+        p { b { "Revisions of section " mono { (revisions.id) } } }
+        ul {
+            @for revision in &revisions.revisions {
+                li {
+                    mono { (revision.number) }
+                    " by "
+                    mono { (revision.author) }
+                    " on "
+                    utc { (revision.timestamp) }
+                    " — "
+                    span.added { "+" (revision.added) }
+                    " "
+                    span.removed { "−" (revision.removed) }
+                }
+            }
+        }
+    }
+}
+
+pub fn revision_diff(id: u32, from: usize, to: usize, lines: Vec<DiffLine>) -> maud::Markup {
+    html! {
+        p { b { "Section " mono { (id) } " from revision " (from) " to " (to) } }
+        @if lines.is_empty() {
+            p { "No changes" }
+        }
+        .diff {
+            @for line in &lines {
+                @match line {
+                    DiffLine::Unchanged(line) => p { (PreEscaped(line)) },
+                    DiffLine::Added(line) => p.added { "+ " (PreEscaped(line)) },
+                    DiffLine::Removed(line) => p.removed { "− " (PreEscaped(line)) },
+                    DiffLine::Skipped(count) => p.skipped { "(" (count) " unchanged lines)" },
+                }
+            }
+        }
+    }
+}
+
 pub fn add_review() -> maud::Markup {
     html! {
         label { "Album ID" }
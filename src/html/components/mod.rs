@@ -183,31 +183,70 @@ pub fn thread(user: &User, section: u32, line: usize) -> Markup {
 
     let thread = section.comments(line);
 
-    let comment_html = |comment: &Comment| {
-        let author = comment.author.first_name();
-        if !comment.show {
-            return html! {
-                .comment.hidden {
-                    (author)
-                    " has removed a message"
-                    @if &comment.author == user {
-                        .unremove edat_uuid=(comment.uuid) { "Restore" }
-                    }
+    let input = html! {
+        #comment-input {
+            #comment-instructions {
+                "Reminder: Please make replying to readers a secondary goal."
+            }
+            textarea #user-comment placeholder="Say something about the entry text…" {}
+        }
+    };
+    
+    html! {
+        #comments {
+            @if thread.comments.len() == 0 {
+                #no-comments.comment {
+                    p { "No comments" }
+                    p #close-comments { "✕" }
                 }
-            };
+                #line-reactions { (reactions(user, &section, Target::Line(line))) }
+            } @else {
+                #some-comments.comment {
+                    p { "Comments" }
+                    p #close-comments { "✕" }
+                }
+                #line-reactions { (reactions(user, &section, Target::Line(line))) }
+                @for comment in thread.roots() {
+                    (thread_comment(user, &section, &thread, comment))
+                }
+            }
         }
+        (input)
+    }
+}
 
+/// Highlight the users mentioned in a comment's text.
+fn with_mentions(index: &Index, text: &str) -> String {
+    comments::mention_regex()
+        .replace_all(text, |c: &regex::Captures| {
+            match index.user_by_mention(&c[2]) {
+                Some(user) => {
+                    let mention = html! { span.mention title=(user.full_name()) { "@" (&c[2]) } };
+                    format!("{}{}", &c[1], mention.into_string())
+                }
+                None => c[0].to_owned(),
+            }
+        })
+        .into_owned()
+}
+
+/// A comment in a thread, followed by the chain of replies to it.
+fn thread_comment(user: &User, section: &Section, thread: &Thread, comment: &Comment) -> Markup {
+    let author = comment.author.first_name();
+    let comment_html = if comment.show {
         html! {
-            .comment {
+            .comment edat_uuid=(comment.uuid) {
                 .text {
-                    (PreEscaped(comment.content.last().unwrap()))
+                    (PreEscaped(with_mentions(section.index(), comment.content.last().unwrap())))
                 }
                 .info {
-                    .author { (comment.author.first_name()) }
+                    .author { (author) }
                     utc.date { (comment.timestamp) }
                     @if comment.content.len() > 1 {
                         .edited { "Edited" }
                     }
+                    .reply edat_uuid=(comment.uuid) edat_author=(author) { "Reply" }
+                    (reactions(user, section, Target::Comment(comment.uuid)))
                     @if &comment.author == user {
                         .span .user-controls {
                             .remove edat_uuid=(comment.uuid) { "Remove" }
@@ -217,35 +256,61 @@ pub fn thread(user: &User, section: u32, line: usize) -> Markup {
                 }
             }
         }
-    };
-    
-    let input = html! {
-        #comment-input {
-            #comment-instructions {
-                "Reminder: Please make replying to readers a secondary goal."
+    } else {
+        html! {
+            .comment.hidden edat_uuid=(comment.uuid) {
+                (author)
+                " has removed a message"
+                @if &comment.author == user {
+                    .unremove edat_uuid=(comment.uuid) { "Restore" }
+                }
             }
-            textarea #user-comment placeholder="Say something about the entry text…" {}
         }
     };
-    
+
+    let replies: Vec<_> = thread.replies(comment.uuid).collect();
     html! {
-        #comments {
-            @if thread.comments.len() == 0 {
-                #no-comments.comment {
-                    p { "No comments" }
-                    p #close-comments { "✕" }
-                }
-            } @else {
-                #some-comments.comment {
-                    p { "Comments" }
-                    p #close-comments { "✕" }
+        (comment_html)
+        @if !replies.is_empty() {
+            .replies {
+                @for reply in replies {
+                    (thread_comment(user, section, thread, reply))
                 }
-                @for comment in thread.comments {
-                    (comment_html(&comment))
+            }
+        }
+    }
+}
+
+/// Buttons for each reaction to a line or comment, with how many left it.
+fn reactions(user: &User, section: &Section, target: Target) -> Markup {
+    let reactions = section.reactions(target);
+    html! {
+        .reactions {
+            @for reaction in Reaction::ALL {
+                @let users = reactions
+                    .iter()
+                    .find(|(r, _)| *r == reaction)
+                    .map_or(&[][..], |(_, users)| users);
+                @let names: Vec<_> = users.iter().map(|u| u.first_name()).collect();
+                span.reaction .used[!users.is_empty()] .mine[users.contains(user)]
+                    edat_reaction=(reaction.name()) title=(names.join(", ")) {
+                    (reaction.emoji())
+                    @if !users.is_empty() {
+                        " " (users.len())
+                    }
                 }
             }
         }
-        (input)
+    }
+}
+
+pub fn tags(tags: &[String]) -> Markup {
+    html! {
+        p.tags {
+            @for tag in tags {
+                a.tag href={ "/tag/" (tag) } { "#" (tag) }
+            }
+        }
     }
 }
 
@@ -299,6 +364,12 @@ pub fn widget_options(widgets: &[String]) -> Markup {
                 order: order(&"extras-widget"),
                 id: "extras-widget".to_owned(),
             },
+            W {
+                name: "Notifications".to_owned(),
+                description: "New entries and replies to your comments".to_owned(),
+                order: order(&"notifications-widget"),
+                id: "notifications-widget".to_owned(),
+            },
             W {
                 name: "Global search".to_owned(),
                 description: "Site-wide search for text content".to_owned(),
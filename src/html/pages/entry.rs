@@ -115,6 +115,7 @@ pub fn entry(
 
             let thread = section.comments(line_index);
             let commenters: HashSet<_> = thread.comments.iter().map(|c| &c.author).collect();
+            let reactions = section.reactions(Target::Line(line_index));
 
             html! {
                 p.textline edat_line=(line_index) .here[jump_here] .here-section[jump_section] {
@@ -122,11 +123,23 @@ pub fn entry(
                     @if commenters.len() > 0 {
                         span.open-comments { " ●" }
                     }
+                    @if !reactions.is_empty() {
+                        span.line-reactions {
+                            @for (reaction, users) in &reactions {
+                                " " (reaction.emoji()) (users.len())
+                            }
+                        }
+                    }
                 }
             }
         });
 
         let show_timestamp = entry.parent_volume().kind() == VolumeKind::Journal;
+        let perspectives: Vec<_> = section
+            .perspectives()
+            .into_iter()
+            .filter(|p| p.status() != section::Status::Missing)
+            .collect();
 
         html! {
             .section edat_section=(section.id()) {
@@ -142,6 +155,26 @@ pub fn entry(
                             (line)
                         }
                     }
+                    @if !section.tags().is_empty() {
+                        .section-tags { (components::tags(section.tags())) }
+                    }
+                    @if !perspectives.is_empty() {
+                        .perspectives {
+                            p.label { "Other perspectives" }
+                            @for perspective in &perspectives {
+                                @let entry = perspective.parent_entry();
+                                a.perspective href={ "/section/" (perspective.id()) } {
+                                    h4 { (PreEscaped(entry.title())) }
+                                    p.description { (PreEscaped(perspective.description())) }
+                                    p.info {
+                                        (entry.author().full_name())
+                                        " in "
+                                        (PreEscaped(entry.parent_volume().title()))
+                                    }
+                                }
+                            }
+                        }
+                    }
                     @if !complete {
                         .aside {
                             p { b { "Incomplete section" } }
@@ -166,6 +199,9 @@ pub fn entry(
 
     let body = html! {
         h2.page-title { (PreEscaped(entry.title())) }
+        @if !entry.tags().is_empty() {
+            .entry-tags { (components::tags(entry.tags())) }
+        }
         a.volume href={ "/volume/" (entry.parent_volume_id()) } {
             (volume)
         }
@@ -203,6 +239,9 @@ pub fn entry(
                     }
                 }
             }
+            p.download {
+                a href={ "/print/entry/" (entry.id()) } { "Printable version" }
+            }
         }
     };
 
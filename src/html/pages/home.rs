@@ -1,4 +1,3 @@
-use std::fs;
 
 use chrono::Utc;
 use rand::prelude::SliceRandom;
@@ -7,7 +6,11 @@ use super::*;
 use crate::data::volume::Kind as VolumeKind;
 
 pub fn home<'index>(headers: &HeaderMap, user: &User) -> maud::Markup {
-    let introduction = fs::read_to_string("content/edat.intro").unwrap();
+    let introduction = user
+        .index()
+        .storage()
+        .read_to_string("content/edat.intro")
+        .unwrap();
 
     let widgets = user.widgets().iter().map(|w| match w.as_ref() {
         "recent-widget" => recent_widget(user),
@@ -17,6 +20,7 @@ pub fn home<'index>(headers: &HeaderMap, user: &User) -> maud::Markup {
         "random-widget" => random_widget(user),
         "extras-widget" => extras_widget(user),
         "search-widget" => search_widget(user.index()),
+        "notifications-widget" => notifications_widget(user),
         _ => html! {},
     });
 
@@ -71,8 +75,8 @@ fn recent_widget(user: &User) -> Markup {
         })
         .collect();
 
-    // Sort them by recency.
-    sections.sort_by_key(|s| (s.date(), s.index_in_parent()));
+    // Sort them by when they were released, not when they were written.
+    sections.sort_by_key(|s| (s.completed(), s.date(), s.index_in_parent()));
     sections.reverse();
 
     // Take only the first 10.
@@ -231,6 +235,38 @@ fn recent_widget(user: &User) -> Markup {
     }
 }
 
+fn notifications_widget(user: &User) -> Markup {
+    let unread = user.unread_notifications();
+
+    // Show the three latest the user hasn't seen.
+    let notifications: Vec<_> = user
+        .notifications()
+        .iter()
+        .rev()
+        .filter(|n| !n.read)
+        .filter_map(|n| notifications::notification(user.index(), n))
+        .take(3)
+        .collect();
+
+    html! {
+        .widget #notifications-widget {
+            h2 {
+                "Notifications"
+                @if unread > 0 {
+                    span.count { (unread) }
+                }
+            }
+            @if notifications.is_empty() {
+                p.nothing { "You’re all caught up." }
+            }
+            @for notification in notifications {
+                (notification)
+            }
+            a.see-all href="/notifications" { "See all notifications" }
+        }
+    }
+}
+
 fn library_widget(user: &User) -> Markup {
     let volumes = user
         .index()
@@ -330,7 +366,19 @@ fn conversations_widget(user: &User) -> Markup {
         .iter()
         .map(|(s, t)| {
             let content = s.content();
-            let comment = t.comments.iter().rev().find(|c| c.show).unwrap();
+
+            // Prefer the latest reply to one of the user's comments.
+            let visible = || t.comments.iter().rev().filter(|c| c.show);
+            let comment = visible()
+                .find(|c| {
+                    &c.author != user
+                        && c.reply_to
+                            .and_then(|uuid| t.comment(uuid))
+                            .is_some_and(|parent| &parent.author == user)
+                })
+                .or_else(|| visible().next())
+                .unwrap();
+            let replying_to = comment.reply_to.and_then(|uuid| t.comment(uuid));
             let mut comment_text = comment.content.last().unwrap().to_owned();
             if comment_text.len() > 150 {
                 comment_text = format!("{}…", &comment_text[..150]);
@@ -431,6 +479,16 @@ fn conversations_widget(user: &User) -> Markup {
                             }
                             .info {
                                 .author { (comment.author.first_name()) }
+                                @if let Some(parent) = replying_to {
+                                    .reply-to.to-user[&parent.author == user] {
+                                        "to "
+                                        @if &parent.author == user {
+                                            "you"
+                                        } @else {
+                                            (parent.author.first_name())
+                                        }
+                                    }
+                                }
                                 utc.date { (comment.timestamp) }
                                 @if comment.content.len() > 1 {
                                     .edited { "Edited" }
@@ -4,8 +4,11 @@ pub mod entry;
 pub mod music;
 pub mod history;
 pub mod home;
+pub mod notifications;
 pub mod profile;
 pub mod search;
+pub mod submit;
+pub mod tag;
 pub mod volume;
 
 pub fn login(headers: &HeaderMap, title: Option<String>, show_panel: bool) -> Markup {
@@ -78,6 +81,24 @@ pub fn setup(headers: &HeaderMap, index: &Index) -> Markup {
     wrappers::universal(setup, headers, "setup", "Setup account", false)
 }
 
+/// Page for turning off a user's email digest from the link in it.
+///
+/// Asks first, since mail scanners follow links, and confirms once `done`.
+pub fn unsubscribe(headers: &HeaderMap, user: &User, done: bool) -> Markup {
+    let body = html! {
+        h1 { "Every Day’s a Thursday" }
+        @if done {
+            p { "You won’t get any more email digests, " (user.first_name()) ". You can turn them back on in your profile settings." }
+        } @else {
+            p { "Stop sending email digests to " (user.first_name()) "?" }
+            form method="post" {
+                button type="submit" { "Unsubscribe" }
+            }
+        }
+    };
+    wrappers::universal(body, headers, "unsubscribe", "Unsubscribe", false)
+}
+
 pub fn terminal(headers: &HeaderMap, allowed: bool) -> maud::Markup {
     let body = if allowed {
         html! {
@@ -0,0 +1,104 @@
+use super::*;
+
+/// Page listing everything the user has been told about, newest first.
+pub fn notifications(headers: &HeaderMap, user: &User) -> Markup {
+    let notifications: Vec<_> = user
+        .notifications()
+        .iter()
+        .rev()
+        .filter_map(|n| notification(user.index(), n))
+        .collect();
+
+    let body = html! {
+        h2 { "Notifications" }
+        @if user.unread_notifications() > 0 {
+            button #read-all { "Mark all as read" }
+        }
+        @if notifications.is_empty() {
+            p.empty { "Nothing yet. You’ll hear here about new entries, replies to your comments, and mentions." }
+        }
+        #notifications {
+            @for notification in notifications {
+                (notification)
+            }
+        }
+    };
+
+    let body = wrappers::standard(body, Vec::new(), None);
+
+    wrappers::universal(body, headers, "notifications", "Notifications", false)
+}
+
+/// A notification linking to what it is about.
+///
+/// Returns [`None`] if that no longer exists.
+pub(super) fn notification(index: &Index, notification: &Notification) -> Option<Markup> {
+    let first_name = |id: &str| {
+        index
+            .user(id.to_owned())
+            .map_or_else(|_| "Someone".to_owned(), |u| u.first_name().to_owned())
+    };
+
+    let (link, text, description) = match &notification.event {
+        Event::Entry { entry } => {
+            let entry = index.entry(entry.clone()).ok()?;
+            (
+                format!("/entry/{}", entry.id()),
+                html! { "New entry " i { (PreEscaped(entry.title())) } " in " (PreEscaped(entry.parent_volume().title())) },
+                entry.description().to_owned(),
+            )
+        }
+        Event::Section { section } => {
+            let section = index.section(*section).ok()?;
+            (
+                format!("/section/{}", section.id()),
+                html! { "New section in " i { (PreEscaped(section.parent_entry().title())) } },
+                section.description().to_owned(),
+            )
+        }
+        Event::Comment {
+            section,
+            line,
+            author,
+        } => {
+            let section = index.section(*section).ok()?;
+            (
+                format!("/section/{}?line={line}", section.id()),
+                html! { (first_name(author)) " commented on " i { (PreEscaped(section.parent_entry().title())) } },
+                section.description().to_owned(),
+            )
+        }
+        Event::Reply {
+            section,
+            line,
+            author,
+        } => {
+            let section = index.section(*section).ok()?;
+            (
+                format!("/section/{}?line={line}", section.id()),
+                html! { (first_name(author)) " replied to your comment on " i { (PreEscaped(section.parent_entry().title())) } },
+                section.description().to_owned(),
+            )
+        }
+        Event::Mention {
+            section,
+            line,
+            author,
+        } => {
+            let section = index.section(*section).ok()?;
+            (
+                format!("/section/{}?line={line}", section.id()),
+                html! { (first_name(author)) " mentioned you on " i { (PreEscaped(section.parent_entry().title())) } },
+                section.description().to_owned(),
+            )
+        }
+    };
+
+    Some(html! {
+        a.notification .unread[!notification.read] href=(link) data-id=(notification.id) {
+            p.text { (text) }
+            p.description { (PreEscaped(description)) }
+            p.info { utc { (notification.timestamp) } }
+        }
+    })
+}
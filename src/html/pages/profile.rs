@@ -17,6 +17,19 @@ pub fn profile(headers: &HeaderMap, user: &User) -> Markup {
     let history_preview: Vec<_> = sections.by_ref().take(history_preview_length).collect();
     let history_rest: Vec<_> = sections.by_ref().skip(history_preview_length).collect();
 
+    let followed_volumes = user.followed_volumes();
+    let email = user.preferences().get("email").map_or("", |e| e.as_str());
+    let digest = user.preferences().get("digest").map_or("", |d| d.as_str());
+
+    // Show the user's own submissions, most recently saved first.
+    let mut submissions: Vec<_> = user
+        .index()
+        .submissions()
+        .into_iter()
+        .filter(|s| s.author == user.id())
+        .collect();
+    submissions.sort_by_key(|s| std::cmp::Reverse(s.updated));
+
     let profile = html! {
         h1 { a href="/" { "Every Day’s a Thursday" } }
         #homepage.module {
@@ -46,10 +59,68 @@ pub fn profile(headers: &HeaderMap, user: &User) -> Markup {
             }
             p.expand #history-expand { "Show more" }
         }
+        #feed.module {
+            h2 { "Feed" }
+            .wrapper {
+                p { "Follow new sections in a feed reader. Anyone with the link can read the feed, so keep it to yourself. If it gets out, make a new link and the old one will stop working." }
+                @if let Some(token) = user.feed_token() {
+                    input #feed-link readonly value={ "/feed/" (token) };
+                    .feed-buttons {
+                        button #feed-reset { "New link" }
+                        button #feed-off { "Turn off" }
+                    }
+                } @else {
+                    .feed-buttons {
+                        button #feed-on { "Turn on" }
+                    }
+                }
+                p { "Only include sections from the volumes checked below. Leave them all unchecked to include every volume." }
+                #feed-volumes {
+                    @for volume in user.index().volumes() {
+                        label {
+                            input type="checkbox" value=(volume.id())
+                                checked[followed_volumes.iter().any(|v| v == volume.id())];
+                            (PreEscaped(volume.title()))
+                        }
+                    }
+                }
+            }
+        }
+        #digest.module {
+            h2 { "Email digest" }
+            .wrapper {
+                p { "Get an email with the new sections in the volumes you follow and the comments on your threads. Every email has a link to stop them." }
+                label for="digest-email" { "Email address" }
+                input #digest-email type="email" value=(email);
+                label for="digest-frequency" { "How often" }
+                select #digest-frequency {
+                    option value="" selected[digest.is_empty()] { "Never" }
+                    option value="daily" selected[digest == "daily"] { "Daily" }
+                    option value="weekly" selected[digest == "weekly"] { "Weekly" }
+                }
+            }
+        }
         #contributions.module {
             h2 { "Contributions" }
-            p { "The ability to write featured content is coming soon, including the Perspectives feature
-                and Personal Journals. Your hub for writing and editing will be right here." }
+            .wrapper {
+                p { "Write an entry for one of the featured volumes. Once it’s approved, it will be published with you as the author." }
+                @for submission in &submissions {
+                    a.submission href={ "/submit/" (submission.id) } {
+                        h3 {
+                            @if submission.draft.title.is_empty() {
+                                "Untitled"
+                            } @else {
+                                (PreEscaped(&submission.draft.title))
+                            }
+                        }
+                        p.info {
+                            span.status { (submit::status(submission)) }
+                            span.updated { "Saved " utc { (submission.updated) } }
+                        }
+                    }
+                }
+                a.new-submission href="/submit" { "Start a new entry" }
+            }
         }
         button #home { "Go back" }
     };
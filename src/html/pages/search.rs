@@ -13,6 +13,14 @@ pub fn search(headers: &HeaderMap, index: &Index, words: &[&str]) -> Markup {
         unique_words
     };
 
+    // Words like `tag:travel` limit the results to content with that tag.
+    let query = words.join(" ");
+    let (tags, words): (Vec<_>, Vec<_>) = words.into_iter().partition(|w| w.starts_with("tag:"));
+    let tags: Vec<_> = tags
+        .into_iter()
+        .map(|t| t.trim_start_matches("tag:").to_lowercase())
+        .collect();
+
     struct Result {
         score: f64,
         all_found: bool,
@@ -31,7 +39,7 @@ pub fn search(headers: &HeaderMap, index: &Index, words: &[&str]) -> Markup {
     let mut results = Vec::new();
     let mut total_hits = 0;
 
-    for volume in index.volumes() {
+    for volume in index.volumes().filter(|_| tags.is_empty()) {
         let search_results = volume.search_index().words(&words);
         let title_hits = search_results.for_section("TITLE").unwrap();
         let subtitle_hits = search_results.for_section("SUBTITLE").unwrap();
@@ -112,9 +120,11 @@ pub fn search(headers: &HeaderMap, index: &Index, words: &[&str]) -> Markup {
         let description_hits = search_results.for_section("DESCRIPTION").unwrap();
         let summary_hits = search_results.for_section("SUMMARY").unwrap();
 
+        let entry_tagged = tags.iter().all(|t| entry.has_tag(t));
         let sections: Vec<_> = entry
             .sections()
             .filter(|s| s.status() != section::Status::Missing)
+            .filter(|s| entry_tagged || tags.iter().all(|t| s.has_tag(t)))
             .map(|s| {
                 let results = s.search_index().words(&words);
                 (s, results)
@@ -125,6 +135,9 @@ pub fn search(headers: &HeaderMap, index: &Index, words: &[&str]) -> Markup {
         if search_results.total_hit_count() == 0 && !any_section_hits {
             continue;
         }
+        if !entry_tagged && sections.is_empty() {
+            continue;
+        }
 
         let mut subresults = Vec::new();
 
@@ -230,11 +243,14 @@ pub fn search(headers: &HeaderMap, index: &Index, words: &[&str]) -> Markup {
             p.hit-count {
                 @if words.len() > 0 {
                     (total_hits) " hits for"
+                } @else if let [tag] = tags.as_slice() {
+                    "Search for a word or phrase tagged "
+                    a href={ "/tag/" (tag) } { "#" (tag) }
                 } @else {
                     "Search for a word or phrase below"
                 }
             }
-            input #search-input type="text" value={(words.join(" "))};
+            input #search-input type="text" value=(query);
         }
         @if !results.is_empty() {
             .carousel-header {
@@ -0,0 +1,87 @@
+use super::*;
+
+/// Page for writing a submission for a featured volume, or looking over one
+/// that can no longer be changed.
+pub fn submit(headers: &HeaderMap, submission: Option<&Submission>) -> Markup {
+    let editable = submission.is_none_or(Submission::editable);
+    let draft = submission.map(|s| &s.draft);
+    let title = draft.map_or("", |d| &d.title);
+    let description = draft.map_or("", |d| &d.description);
+    let summary = draft.map_or("", |d| &d.summary);
+    let content = draft.map_or("", |d| &d.content);
+
+    let body = html! {
+        h2 {
+            @if title.is_empty() {
+                "New entry"
+            } @else {
+                (PreEscaped(title))
+            }
+        }
+        @if let Some(submission) = submission {
+            p.status { (status(submission)) }
+            @if !submission.note.is_empty() {
+                p.note { "From the editor: " (PreEscaped(&submission.note)) }
+            }
+        }
+        @if editable {
+            #draft data-id=[submission.map(|s| s.id)] {
+                p.instructions {
+                    "Write an entry for one of the featured volumes. Save a draft as you go, and submit it for review when it’s ready. Once it’s approved, it will be published with you as the author."
+                }
+                label for="draft-title" { "Title" }
+                input #draft-title maxlength="30" value=(PreEscaped(title));
+                label for="draft-description" { "Description" }
+                textarea #draft-description maxlength="70" { (PreEscaped(description)) }
+                label for="draft-summary" { "Summary" }
+                textarea #draft-summary maxlength="150" { (PreEscaped(summary)) }
+                label for="draft-content" { "Text" }
+                textarea #draft-content { (PreEscaped(content)) }
+            }
+        } @else {
+            #draft.locked {
+                p.description { (PreEscaped(description)) }
+                .content {
+                    @for line in content.lines() {
+                        p { (PreEscaped(line)) }
+                    }
+                }
+            }
+        }
+        .buttons {
+            @if editable {
+                button #save { "Save draft" }
+                button #submit { "Submit for review" }
+            }
+            @if let Some(entry) = submission.and_then(|s| s.entry.as_ref()) {
+                a href={ "/entry/" (entry) } { "Read it in the journal" }
+            } @else if submission.is_some() {
+                button #withdraw { "Delete" }
+            }
+        }
+    };
+
+    let body = wrappers::standard(body, Vec::new(), None);
+
+    wrappers::universal(body, headers, "submit", "Contribute", false)
+}
+
+pub fn error(headers: &HeaderMap, id: u32) -> Markup {
+    let body = html! {
+        h1 { "Every Day’s a Thursday" }
+        p { "You don’t have a submission with the id " mono { (id) } "." }
+    };
+
+    wrappers::universal(body, headers, "missing_id", "Content not found", false)
+}
+
+/// Where a submission is in the review process, as its author sees it.
+pub(super) fn status(submission: &Submission) -> &'static str {
+    match submission.status {
+        submission::Status::Draft => "Draft",
+        submission::Status::Submitted => "Awaiting review",
+        submission::Status::ChangesRequested => "Changes requested",
+        submission::Status::Rejected => "Not accepted",
+        submission::Status::Approved => "Published",
+    }
+}
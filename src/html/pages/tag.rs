@@ -0,0 +1,76 @@
+use super::*;
+
+pub fn tag(headers: &HeaderMap, user: &User, tag: &str) -> Markup {
+    let index = user.index();
+
+    let mut entries_html = Vec::new();
+    for (entry, sections) in index.tagged(tag) {
+        let volume = entry.parent_volume();
+        entries_html.push(html! {
+            a.section.new-entry href={ "/entry/" (entry.id()) } {
+                h3 { (PreEscaped(entry.title())) }
+                p.description { (PreEscaped(entry.description())) }
+                p.info {
+                    span { (PreEscaped(volume.title())) }
+                    @if entry.has_tag(tag) {
+                        span { "Whole entry" }
+                    }
+                }
+            }
+        });
+
+        for section in sections {
+            if section.status() == section::Status::Missing {
+                continue;
+            }
+            let unread = user.section_progress(&section).is_none();
+            entries_html.push(html! {
+                a.section href={ "/section/" (section.id()) } {
+                    p.description { (PreEscaped(section.description())) }
+                    p.info {
+                        @if entry.section_count() > 1 {
+                            span.section-index { "Section " (1 + section.index_in_parent()) }
+                        } @else {
+                            span.section-index { "Standalone" }
+                        }
+                        span.word_count { (section.length_string()) " words" }
+                        span.date { "Added " (crate::data::date_string(&section.date())) }
+                        @if unread {
+                            span.unread { "Unread" }
+                        }
+                    }
+                }
+            });
+        }
+    }
+
+    let other_tags: Vec<_> = index
+        .tags()
+        .into_iter()
+        .map(|(t, _)| t)
+        .filter(|t| t != tag)
+        .collect();
+
+    let body = html! {
+        h2 { "#" (tag) }
+        @if entries_html.is_empty() {
+            p.empty { "Nothing is tagged " mono { (tag) } " yet." }
+        }
+        #sections {
+            @for entry in entries_html {
+                (entry)
+            }
+        }
+        a.search href={ "/search/tag:" (tag) } { "Search within this tag" }
+        @if !other_tags.is_empty() {
+            #other-tags {
+                h4 { "Other tags" }
+                (components::tags(&other_tags))
+            }
+        }
+    };
+
+    let body = wrappers::standard(body, Vec::new(), None);
+
+    wrappers::universal(body, headers, "tag", &format!("#{tag}"), false)
+}
@@ -100,6 +100,11 @@ pub fn volume(headers: &HeaderMap, volume: &Volume, user: &User) -> Markup {
                 }
             }
         }
+        p.download {
+            a href={ "/epub/" (volume.id()) } { "Download for e-readers" }
+            " · "
+            a href={ "/print/volume/" (volume.id()) } { "Printable version" }
+        }
     };
 
     // Gather unfinished entry suggestions.
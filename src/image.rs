@@ -1,4 +1,4 @@
-use std::fs;
+use std::{fs, path::Path};
 
 use image_builder::{FilterType, Image, Picture, Text};
 
@@ -6,7 +6,7 @@ const BACKGROUND: [u8; 4] = [0x15, 0x18, 0x1f, 0xff];
 const CONTENT: [u8; 4] = [0xf0, 0xf2, 0xf5, 0xff];
 const GRAY: [u8; 4] = [0x4c, 0x50, 0x59, 0xff];
 
-pub fn entry_image(name: &str, description: &str) {
+pub fn entry_image(name: &str, description: &str, path: &Path) {
     let width = 1250;
     let height = 600;
 
@@ -59,14 +59,15 @@ pub fn entry_image(name: &str, description: &str) {
 
     add_line(&line);
 
-    image.save("content/preview.png");
+    image.save(&path.to_string_lossy());
 }
 
 pub fn section_image(
     name: &str,
     description: &str,
     section_index: &str,
-    date: &str
+    date: &str,
+    path: &Path,
 ) {
     let width = 1250;
     let height = 640;
@@ -136,5 +137,5 @@ pub fn section_image(
             .color(GRAY),
     );
 
-    image.save("content/preview.png");
+    image.save(&path.to_string_lossy());
 }
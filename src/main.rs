@@ -1,44 +1,172 @@
-use std::{sync::Arc, time::Instant};
+use std::{
+    path::Path,
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
 use axum::{
+    extract::DefaultBodyLimit,
     routing::{delete, get, post},
     Router,
 };
-use data::music::{SpotifyCredentials, SpotifyData};
-use tokio::{net::TcpListener, sync::Mutex};
+use config::Config;
+use data::{
+    markdown, migration,
+    music::{SpotifyCredentials, SpotifyData},
+    snapshot,
+    storage::{self, Storage},
+};
+use tokio::{
+    net::TcpListener,
+    sync::{Mutex, RwLock},
+};
 
+mod book;
+mod config;
 mod data;
+mod digest;
+mod feed;
 mod html;
 mod image;
 mod routes;
 mod search;
 
+/// The largest archive zip that can be uploaded for a restore, in bytes.
+const ARCHIVE_UPLOAD_LIMIT: usize = 1024 * 1024 * 1024;
+
 #[tokio::main]
 async fn main() {
     let index_load_start = Instant::now();
-    let mut index = data::Index::init();
-    if let Some(arg) = std::env::args().nth(1) {
-        if arg == "reindex" {
-            index.save_all();
+    let config = Config::load();
+    let args: Vec<_> = std::env::args().skip(1).collect();
+
+    // Copy the data directory into a database file.
+    if let [command, database] = &args[..] {
+        if command == "convert" {
+            let from = storage::Directory::open(&config.data_root)
+                .expect("error opening data directory");
+            let to = storage::Sqlite::open(database).expect("error opening database");
+            let count = storage::copy(&from, &to).expect("error copying data");
+            println!("Copied {count} files into {database}");
+            return;
+        }
+    }
+
+    // Round-trip the journal through a directory of Markdown files.
+    if let [command, dir] = &args[..] {
+        if command == "export-markdown" {
+            let index = data::Index::init(open_storage(&config));
+            let count = markdown::export(&index, Path::new(dir)).expect("error exporting journal");
+            println!("Exported {count} sections into {dir}");
+            return;
+        }
+        if command == "import-markdown" {
+            let mut index = data::Index::init(open_storage(&config));
+            let summary =
+                markdown::import(&mut index, Path::new(dir)).expect("error importing journal");
+            println!(
+                "Imported {dir}: {} created, {} updated, {} moved to the trash",
+                summary.created, summary.updated, summary.removed
+            );
+            return;
+        }
+    }
+
+    // Upgrade the journal data to the current schema.
+    if args.first().is_some_and(|a| a == "migrate") {
+        let storage = open_storage(&config);
+        let version =
+            migration::schema_version(storage.as_ref()).expect("error reading index file");
+        println!("Data is at schema version {version}");
+        let count = migration::migrate(storage.as_ref()).expect("error migrating journal data");
+        println!(
+            "Applied {count} migrations; data is at schema version {}",
+            migration::SCHEMA_VERSION
+        );
+        return;
+    }
+
+    // Report problems in the journal data instead of serving it.
+    if args.first().is_some_and(|a| a == "check") {
+        let fix = args.iter().any(|a| a == "--fix");
+        let problems = data::check::check(open_storage(&config), fix);
+        for problem in &problems {
+            println!("{problem}");
         }
+        let unfixed = problems.iter().filter(|p| !p.fixed).count();
+        println!(
+            "{} problems found, {} fixed",
+            problems.len(),
+            problems.len() - unfixed
+        );
+        if unfixed > 0 {
+            std::process::exit(1);
+        }
+        return;
     }
 
-    let mut spotify_credentials = SpotifyCredentials::fresh().await;
+    let mut index = data::Index::init(open_storage(&config));
+    if args.first().is_some_and(|a| a == "reindex") {
+        index.save_all();
+    }
+    if let Some(cutoff) = config.trash_cutoff() {
+        let purged = index.purge_trash(cutoff);
+        if purged > 0 {
+            println!("Purged {purged} items from the trash");
+        }
+    }
 
-    SpotifyData::refresh_file(&index, spotify_credentials.access_token().await.to_owned()).await;
+    let spotify_credentials = if config.spotify {
+        let mut spotify_credentials = SpotifyCredentials::fresh(
+            &config.spotify_client_id,
+            &config.spotify_client_secret,
+        )
+        .await;
+        SpotifyData::refresh_file(&index, spotify_credentials.access_token().await.to_owned())
+            .await;
+        Some(Arc::new(Mutex::new(spotify_credentials)))
+    } else {
+        None
+    };
 
     let index_load_elapsed = index_load_start.elapsed();
     println!("Index loaded in {index_load_elapsed:?}");
 
+    let listener = TcpListener::bind(config.socket_address()).await.unwrap();
+
     let state = AppState {
-        index: Arc::new(Mutex::new(index)),
-        spotify_credentials: Arc::new(Mutex::new(spotify_credentials)),
+        index: Arc::new(RwLock::new(index)),
+        spotify_credentials,
+        config: Arc::new(config),
+        preview_image: Arc::new(Mutex::new(())),
     };
 
+    if let Some(hours) = state.config.snapshot_interval_hours.filter(|&h| h > 0) {
+        tokio::spawn(take_snapshots(state.clone(), hours));
+    }
+    tokio::spawn(publish_scheduled(state.clone()));
+    if state.config.smtp.is_some() {
+        if state.config.site_url.is_some() {
+            tokio::spawn(send_digests(state.clone()));
+        } else {
+            println!("Not sending email digests: site_url is unset");
+        }
+    }
+
     let app = Router::new()
         .route("/", get(routes::pages::home))
         .route("/album/:id", get(routes::pages::album_review))
+        .route("/api/v1/entry/:id", get(routes::api::entry))
+        .route("/api/v1/library", get(routes::api::library))
+        .route("/api/v1/progress", get(routes::api::progress))
+        .route("/api/v1/section/:id", get(routes::api::section))
+        .route("/api/v1/volume/:id", get(routes::api::volume))
         .route("/archive", get(routes::files::archive))
+        .route(
+            "/archive/:file",
+            // Archives hold the whole journal, images included, so they can be large.
+            post(routes::cmd::archive_upload).layer(DefaultBodyLimit::max(ARCHIVE_UPLOAD_LIMIT)),
+        )
         .route("/asset/:file", get(routes::files::asset))
         .route("/cmd", post(routes::cmd::cmd))
         .route(
@@ -51,18 +179,41 @@ async fn main() {
             post(routes::post::edit_comment),
         )
         .route("/entry/:entry", get(routes::pages::entry))
+        .route("/epub/:volume", get(routes::files::epub))
+        .route("/print/volume/:volume", get(routes::files::print_volume))
+        .route("/print/entry/:entry", get(routes::files::print_entry))
         .route("/music", get(routes::pages::music))
+        .route("/feed/:token", get(routes::files::feed))
+        .route(
+            "/feed-token",
+            post(routes::user::reset_feed_token).delete(routes::delete::feed_token),
+        )
+        .route("/feed-volumes", post(routes::user::set_followed_volumes))
         .route("/history", get(routes::pages::history))
         .route("/image/:file", get(routes::files::image))
         .route("/image/:file", post(routes::cmd::image_upload))
         .route("/library", get(routes::pages::volumes))
         .route("/login/:name/:code", post(routes::auth::login))
         .route("/mir/:month" , get(routes::pages::month_in_review))
+        .route("/notifications", get(routes::pages::notifications))
+        .route(
+            "/notifications-read",
+            post(routes::user::read_notifications),
+        )
         .route("/preferences", post(routes::user::set_preferences))
         .route("/preview", get(routes::files::preview))
         .route("/profile", get(routes::pages::profile))
+        .route(
+            "/react/:section/:line/:reaction",
+            post(routes::post::react_line),
+        )
+        .route(
+            "/react_comment/:section/:uuid/:reaction",
+            post(routes::post::react_comment),
+        )
         .route("/read/:id", post(routes::user::read))
         .route("/register", post(routes::user::register))
+        .route("/reply/:section/:uuid", post(routes::post::reply))
         .route(
             "/remove_comment/:section/:uuid",
             delete(routes::delete::comment),
@@ -88,32 +239,164 @@ async fn main() {
         )
         .route("/section/:id", get(routes::pages::entry_by_section))
         .route("/style/:file", get(routes::files::style))
+        .route("/submission", post(routes::user::save_submission))
+        .route("/submission/:id", delete(routes::delete::submission))
+        .route("/submit", get(routes::pages::new_submission))
+        .route("/submit/:id", get(routes::pages::submission))
+        .route("/tag/:tag", get(routes::pages::tag))
         .route("/terminal", get(routes::pages::terminal))
         .route("/thread/:section/:line", get(routes::components::thread))
         .route(
             "/unremove_comment/:section/:uuid",
             post(routes::post::unremove_comment),
         )
+        .route(
+            "/unsubscribe/:token",
+            get(routes::pages::unsubscribe).post(routes::user::unsubscribe),
+        )
         .route("/volume/:volume", get(routes::pages::volume))
         .route("/widgets", post(routes::user::set_widgets))
         .with_state(state);
 
-    let listener = listener().await;
     axum::serve(listener, app).await.unwrap();
 }
 
-#[cfg(debug_assertions)]
-async fn listener() -> TcpListener {
-    TcpListener::bind("0.0.0.0:3000").await.unwrap()
+/// Take a snapshot of the journal data every `hours`, starting now, and
+/// delete the ones no longer retained.
+async fn take_snapshots(state: AppState, hours: u64) {
+    let dir = &state.config.snapshot_dir;
+    let mut interval = tokio::time::interval(Duration::from_secs(hours * 60 * 60));
+    loop {
+        interval.tick().await;
+        let index = state.index.read().await;
+        match snapshot::take(index.storage(), dir) {
+            Ok((snapshot, true)) => println!("Took snapshot {}", snapshot.name),
+            Ok((_, false)) => {}
+            Err(e) => println!("error taking snapshot: {e}"),
+        }
+        drop(index);
+        match snapshot::rotate(dir, &state.config.snapshot_retention) {
+            Ok(0) => {}
+            Ok(deleted) => println!("Deleted {deleted} old snapshots"),
+            Err(e) => println!("error deleting old snapshots: {e}"),
+        }
+    }
+}
+
+/// Mark scheduled sections complete once their time comes, checking every
+/// minute, starting now.
+async fn publish_scheduled(state: AppState) {
+    let mut interval = tokio::time::interval(Duration::from_secs(60));
+    loop {
+        interval.tick().await;
+        // Only hold up page loads when there is something to publish.
+        if !state.index.read().await.any_scheduled_due() {
+            continue;
+        }
+        let mut index = state.index.write().await;
+        for id in index.publish_scheduled() {
+            println!("Published section {id}");
+        }
+    }
+}
+
+/// Email each user who asked for a digest once their day or week is up,
+/// checking every hour, starting now.
+///
+/// A user's first check only starts the clock, so their first digest covers
+/// the period after they asked for it. The clock stops if they unsubscribe.
+async fn send_digests(state: AppState) {
+    let smtp = state.config.smtp.as_ref().unwrap();
+    let site_url = state.config.site_url.as_deref().unwrap();
+    let base = site_url.trim_end_matches('/');
+    let mut interval = tokio::time::interval(Duration::from_secs(60 * 60));
+    loop {
+        interval.tick().await;
+        let now = chrono::Utc::now().timestamp();
+
+        // Write the digests that are due.
+        let mut digests = Vec::new();
+        let mut index = state.index.write().await;
+        let users: Vec<_> = index
+            .users()
+            .map(|u| {
+                let frequency = digest::address(&u).and(digest::Frequency::of(&u));
+                (u.id().to_owned(), u.last_digest(), frequency)
+            })
+            .collect();
+        for (id, last, frequency) in users {
+            let (last, frequency) = match (last, frequency) {
+                (Some(last), Some(frequency)) => (last, frequency),
+                (None, Some(_)) => {
+                    index.user_mut(id).unwrap().set_last_digest(Some(now));
+                    continue;
+                }
+                (Some(_), None) => {
+                    index.user_mut(id).unwrap().set_last_digest(None);
+                    continue;
+                }
+                (None, None) => continue,
+            };
+            // Give or take half an hour, since the checks don't line up with
+            // the period.
+            if last + frequency.period() > now + 30 * 60 {
+                continue;
+            }
+            let token = index.user_mut(id.clone()).unwrap().digest_token_or_new();
+            let user = index.user(id.clone()).unwrap();
+            match digest::compose(&user, last, base, &token) {
+                Some(body) => digests.push((id, digest::address(&user).unwrap(), body)),
+                // Nothing happened, so start the next period.
+                None => index.user_mut(id).unwrap().set_last_digest(Some(now)),
+            }
+        }
+        drop(index);
+
+        // Send them without holding up the website.
+        for (id, to, body) in digests {
+            match digest::send(smtp, to, body).await {
+                Ok(()) => {
+                    println!("Sent digest to {id}");
+                    if let Ok(mut user) = state.index.write().await.user_mut(id) {
+                        user.set_last_digest(Some(now));
+                    }
+                }
+                Err(e) => println!("error sending digest to {id}: {e}"),
+            }
+        }
+    }
 }
 
-#[cfg(not(debug_assertions))]
-async fn listener() -> TcpListener {
-    TcpListener::bind("0.0.0.0:80").await.unwrap()
+/// Open the journal data.
+///
+/// `EDAT_FIXTURE` names a data directory to load into memory, leaving it
+/// untouched by the run. Otherwise, the configured database or data root is
+/// used.
+fn open_storage(config: &Config) -> Arc<dyn Storage> {
+    if let Ok(fixture) = std::env::var("EDAT_FIXTURE") {
+        let directory = storage::Directory::open(fixture).expect("error opening fixture");
+        let memory = storage::Memory::new();
+        storage::copy(&directory, &memory).expect("error loading fixture");
+        return Arc::new(memory);
+    }
+
+    match &config.database {
+        Some(database) => {
+            Arc::new(storage::Sqlite::open(database).expect("error opening database"))
+        }
+        None => Arc::new(
+            storage::Directory::open(&config.data_root).expect("error opening data directory"),
+        ),
+    }
 }
 
 #[derive(Clone)]
 pub struct AppState {
-    index: Arc<Mutex<data::Index>>,
-    spotify_credentials: Arc<Mutex<SpotifyCredentials>>,
+    /// Pages and searches share read access; changes wait for them to finish.
+    index: Arc<RwLock<data::Index>>,
+    /// Missing if the music pages are disabled.
+    spotify_credentials: Option<Arc<Mutex<SpotifyCredentials>>>,
+    config: Arc<Config>,
+    /// Held while the shared preview image is written or read.
+    preview_image: Arc<Mutex<()>>,
 }
@@ -0,0 +1,290 @@
+use serde::Serialize;
+
+use super::*;
+use crate::data::history::SectionProgress;
+
+/// The body of every unsuccessful response.
+#[derive(Serialize)]
+pub struct ApiError {
+    /// What went wrong.
+    pub error: String,
+}
+
+/// A volume as listed in the library.
+#[derive(Serialize)]
+pub struct VolumeSummary {
+    pub id: String,
+    pub title: String,
+    pub subtitle: Option<String>,
+    /// The id of the user who owns the volume.
+    pub owner: String,
+    /// One of `Journal`, `Archive`, `Diary`, `Cartoons`, `Creative` or
+    /// `Featured`.
+    pub kind: volume::Kind,
+    /// How many parts, numbered from 0, the volume's entries are split into.
+    pub parts: usize,
+    pub entry_count: usize,
+}
+
+/// A volume and the structure of its entries.
+#[derive(Serialize)]
+pub struct VolumeDetail {
+    #[serde(flatten)]
+    pub volume: VolumeSummary,
+    /// The introduction, one paragraph per line.
+    pub intro: Vec<String>,
+    /// The entries in order, with the part each belongs to.
+    pub entries: Vec<EntrySummary>,
+}
+
+/// An entry as listed in its volume.
+#[derive(Serialize)]
+pub struct EntrySummary {
+    pub id: String,
+    pub title: String,
+    pub description: String,
+    pub summary: String,
+    /// The id of the user who wrote the entry.
+    pub author: String,
+    pub volume: String,
+    pub part: usize,
+    /// The total length of the entry's text in words.
+    pub length: usize,
+    pub tags: Vec<String>,
+}
+
+/// An entry and its sections.
+#[derive(Serialize)]
+pub struct EntryDetail {
+    #[serde(flatten)]
+    pub entry: EntrySummary,
+    pub sections: Vec<SectionSummary>,
+}
+
+/// A section as listed in its entry.
+#[derive(Serialize)]
+pub struct SectionSummary {
+    pub id: u32,
+    pub heading: Option<String>,
+    pub description: String,
+    pub summary: String,
+    /// The date the section was added, as `YYYY-MM-DD`.
+    pub date: String,
+    /// One of `Missing`, `Incomplete` or `Complete`. Missing sections have no
+    /// text yet.
+    pub status: section::Status,
+    pub entry: String,
+    /// The length of the section's text in words.
+    pub length: usize,
+    pub tags: Vec<String>,
+    /// Sections telling the same events from another perspective.
+    pub perspectives: Vec<u32>,
+    /// The requesting user's progress, or `null` if they haven't opened it.
+    pub progress: Option<Progress>,
+}
+
+/// A section and its text.
+#[derive(Serialize)]
+pub struct SectionDetail {
+    #[serde(flatten)]
+    pub section: SectionSummary,
+    /// The text, one paragraph per line. Lines can hold inline HTML, and block
+    /// commands like `/note` and `/img` are left for the client to render.
+    pub lines: Vec<String>,
+}
+
+/// A user's progress through a section. Times are Unix timestamps.
+#[derive(Serialize)]
+#[serde(tag = "state", rename_all = "snake_case")]
+pub enum Progress {
+    /// Reading for the first time, up to `line`.
+    Reading { line: usize, last_read: i64 },
+    /// Read to the end.
+    Finished { last_read: i64 },
+    /// Reading again, up to `line`.
+    Rereading { line: usize, last_read: i64 },
+}
+
+/// The requesting user and everything they have read.
+#[derive(Serialize)]
+pub struct UserProgress {
+    pub id: String,
+    pub first_name: String,
+    pub last_name: String,
+    /// Progress through every section the user has opened, most recent first.
+    pub sections: Vec<SectionProgressEntry>,
+}
+
+/// Progress through one section.
+#[derive(Serialize)]
+pub struct SectionProgressEntry {
+    pub section: u32,
+    pub entry: String,
+    #[serde(flatten)]
+    pub progress: Progress,
+}
+
+type ApiResult<T> = Result<Json<T>, Response>;
+
+/// `GET /api/v1/library`: every volume in order.
+pub async fn library(
+    headers: HeaderMap,
+    State(state): State<AppState>,
+) -> ApiResult<Vec<VolumeSummary>> {
+    let index = state.index.read().await;
+    authenticate(&headers, &index).ok_or_else(unauthorized)?;
+    Ok(Json(index.volumes().map(|v| volume_summary(&v)).collect()))
+}
+
+/// `GET /api/v1/volume/:id`: a volume and its entries.
+pub async fn volume(
+    headers: HeaderMap,
+    State(state): State<AppState>,
+    ReqPath(id): ReqPath<String>,
+) -> ApiResult<VolumeDetail> {
+    let index = state.index.read().await;
+    authenticate(&headers, &index).ok_or_else(unauthorized)?;
+    let volume = index.volume(id).map_err(missing)?;
+    Ok(Json(VolumeDetail {
+        volume: volume_summary(&volume),
+        intro: volume.intro().lines().map(str::to_owned).collect(),
+        entries: volume.entries().map(|e| entry_summary(&e)).collect(),
+    }))
+}
+
+/// `GET /api/v1/entry/:id`: an entry and its sections.
+pub async fn entry(
+    headers: HeaderMap,
+    State(state): State<AppState>,
+    ReqPath(id): ReqPath<String>,
+) -> ApiResult<EntryDetail> {
+    let index = state.index.read().await;
+    let user = authenticate(&headers, &index).ok_or_else(unauthorized)?;
+    let entry = index.entry(id).map_err(missing)?;
+    Ok(Json(EntryDetail {
+        entry: entry_summary(&entry),
+        sections: entry
+            .sections()
+            .map(|s| section_summary(&s, &user))
+            .collect(),
+    }))
+}
+
+/// `GET /api/v1/section/:id`: a section and its text.
+pub async fn section(
+    headers: HeaderMap,
+    State(state): State<AppState>,
+    ReqPath(id): ReqPath<u32>,
+) -> ApiResult<SectionDetail> {
+    let index = state.index.read().await;
+    let user = authenticate(&headers, &index).ok_or_else(unauthorized)?;
+    let section = index.section(id).map_err(missing)?;
+    let lines = match section.status() {
+        section::Status::Missing => Vec::new(),
+        _ => section.content().lines().map(str::to_owned).collect(),
+    };
+    Ok(Json(SectionDetail {
+        section: section_summary(&section, &user),
+        lines,
+    }))
+}
+
+/// `GET /api/v1/progress`: the requesting user's reading progress.
+pub async fn progress(
+    headers: HeaderMap,
+    State(state): State<AppState>,
+) -> ApiResult<UserProgress> {
+    let index = state.index.read().await;
+    let user = authenticate(&headers, &index).ok_or_else(unauthorized)?;
+    let mut history = user.history();
+    history.sort_by_key(|(_, p)| std::cmp::Reverse(p.timestamp()));
+    Ok(Json(UserProgress {
+        id: user.id().to_owned(),
+        first_name: user.first_name().to_owned(),
+        last_name: user.last_name().to_owned(),
+        sections: history
+            .into_iter()
+            .map(|(section, progress)| SectionProgressEntry {
+                section: section.id(),
+                entry: section.parent_entry_id().to_owned(),
+                progress: progress.into(),
+            })
+            .collect(),
+    }))
+}
+
+impl From<SectionProgress> for Progress {
+    fn from(progress: SectionProgress) -> Self {
+        match progress {
+            SectionProgress::Reading { line, last_read } => Progress::Reading { line, last_read },
+            SectionProgress::Finished { last_read } => Progress::Finished { last_read },
+            SectionProgress::Rereading { line, last_read } => {
+                Progress::Rereading { line, last_read }
+            }
+        }
+    }
+}
+
+/// The user making the request, who has to be set up.
+fn authenticate<'index>(headers: &HeaderMap, index: &'index Index) -> Option<User<'index>> {
+    auth::get_user(headers, index, None, false).ok()
+}
+
+fn unauthorized() -> Response {
+    error(StatusCode::UNAUTHORIZED, "not logged in".to_owned())
+}
+
+fn missing(err: DataError) -> Response {
+    let message = match err {
+        DataError::MissingResource(kind, id) => format!("unknown {kind} {id}"),
+        DataError::DuplicateId(id) => format!("taken id {id}"),
+        DataError::NotSubmitted(id, _) => format!("submission {id} not awaiting review"),
+    };
+    error(StatusCode::NOT_FOUND, message)
+}
+
+fn error(status: StatusCode, error: String) -> Response {
+    (status, Json(ApiError { error })).into_response()
+}
+
+fn volume_summary(volume: &Volume) -> VolumeSummary {
+    VolumeSummary {
+        id: volume.id().to_owned(),
+        title: volume.title().to_owned(),
+        subtitle: volume.subtitle().cloned(),
+        owner: volume.owner_id().to_owned(),
+        kind: volume.kind(),
+        parts: volume.parts_count(),
+        entry_count: volume.entry_count(),
+    }
+}
+
+fn entry_summary(entry: &Entry) -> EntrySummary {
+    EntrySummary {
+        id: entry.id().to_owned(),
+        title: entry.title().to_owned(),
+        description: entry.description().to_owned(),
+        summary: entry.summary().to_owned(),
+        author: entry.author_id().to_owned(),
+        volume: entry.parent_volume_id().to_owned(),
+        part: entry.parent_volume_part(),
+        length: entry.length(),
+        tags: entry.tags().to_owned(),
+    }
+}
+
+fn section_summary(section: &Section, user: &User) -> SectionSummary {
+    SectionSummary {
+        id: section.id(),
+        heading: section.heading().cloned(),
+        description: section.description().to_owned(),
+        summary: section.summary().to_owned(),
+        date: section.date().format("%Y-%m-%d").to_string(),
+        status: section.status(),
+        entry: section.parent_entry_id().to_owned(),
+        length: section.length(),
+        tags: section.tags().to_owned(),
+        perspectives: section.perspectives().iter().map(Section::id).collect(),
+        progress: user.section_progress(section).map(Progress::from),
+    }
+}
@@ -1,23 +1,22 @@
-use std::{
-    fs::{self, File},
-    io::Write,
-    path::Path,
-};
+use std::fs;
 
-use chrono::{NaiveDate, Utc};
+use chrono::{DateTime, NaiveDate, Utc};
 use indexmap::IndexMap;
 use music::{SpotifyCredentials, SpotifyData};
 use serde::Deserialize;
 
 use super::*;
+use crate::config::Config;
 
 pub async fn image_upload(
     headers: HeaderMap,
+    State(state): State<AppState>,
     ReqPath(mut file_name): ReqPath<String>,
     body: Bytes,
 ) -> impl IntoResponse {
-    let image_path = format!("content/images/{file_name}");
-    let image_path = Path::new(&image_path);
+    // The write lock keeps another upload from taking the name between the
+    // check and the write.
+    let index = state.index.write().await;
     let is_jpeg = headers
         .get("Content-Type")
         .is_some_and(|c| c == "image/jpeg");
@@ -26,15 +25,38 @@ pub async fn image_upload(
     if file_name.ends_with(".jpeg") {
         file_name = file_name.replace(".jpeg", ".jpg");
     }
+    let image_path = format!("content/images/{file_name}");
 
-    if !file_name.ends_with(".jpg") || image_path.exists() || !is_jpeg {
+    if !file_name.ends_with(".jpg") || index.storage().exists(&image_path) || !is_jpeg {
         return html::cmd::image_error(&file_name);
     }
-    let mut file = File::create(image_path).unwrap();
-    file.write(&body[..]).unwrap();
+    index.storage().write(&image_path, body.to_vec()).unwrap();
     html::cmd::image_success(&file_name)
 }
 
+pub async fn archive_upload(
+    headers: HeaderMap,
+    State(state): State<AppState>,
+    ReqPath(file_name): ReqPath<String>,
+    body: Bytes,
+) -> impl IntoResponse {
+    // Restoring replaces the journal, so only owners can upload archives.
+    {
+        let index = state.index.read().await;
+        match auth::get_user(&headers, &index, None, false) {
+            Ok(user) if user.privilege() == UserPrivilege::Owner => {}
+            _ => return html::cmd::unauthorized(),
+        }
+    }
+    let path = state.config.archive_dir.join(&file_name);
+    if file_name.contains(['/', '\\']) || !file_name.ends_with(".zip") || path.exists() {
+        return html::cmd::archive_error(&file_name);
+    }
+    fs::create_dir_all(&state.config.archive_dir).unwrap();
+    fs::write(path, body).unwrap();
+    html::cmd::archive_success(&file_name)
+}
+
 #[derive(Deserialize)]
 pub enum Body {
     GetSection {
@@ -68,6 +90,22 @@ pub enum Body {
         id: u32,
         status: section::Status,
     },
+    ScheduleSection {
+        id: u32,
+        publish_at: Option<i64>,
+    },
+    SectionTags {
+        id: u32,
+        tags: Vec<String>,
+    },
+    AddPerspective {
+        id: u32,
+        perspective: u32,
+    },
+    RemovePerspective {
+        id: u32,
+        perspective: u32,
+    },
     GetEntry {
         id: String,
     },
@@ -91,6 +129,10 @@ pub enum Body {
         id: String,
         position: Position<(String, usize), String>,
     },
+    EntryTags {
+        id: String,
+        tags: Vec<String>,
+    },
     GetVolume {
         id: String,
     },
@@ -142,6 +184,7 @@ pub enum Body {
         code: String,
     },
     Volumes,
+    Tags,
     NextSectionId,
     Images,
     GetContent {
@@ -151,6 +194,58 @@ pub enum Body {
         id: u32,
         content: String,
     },
+    Trash,
+    RestoreVolume {
+        id: String,
+        position: Option<Position<(), String>>,
+    },
+    RestoreEntry {
+        id: String,
+        position: Option<Position<(String, usize), String>>,
+    },
+    RestoreSection {
+        id: u32,
+        position: Option<Position<String, u32>>,
+    },
+    PurgeTrash {
+        days: Option<u64>,
+    },
+    Archives,
+    PreviewRestore {
+        archive: String,
+        mode: restore::Mode,
+    },
+    Restore {
+        archive: String,
+        mode: restore::Mode,
+    },
+    Snapshots,
+    TakeSnapshot,
+    Revisions {
+        id: u32,
+    },
+    RevisionDiff {
+        id: u32,
+        from: usize,
+        to: usize,
+    },
+    RestoreRevision {
+        id: u32,
+        revision: usize,
+    },
+    Submissions,
+    GetSubmission {
+        id: u32,
+    },
+    ApproveSubmission {
+        id: u32,
+        position: Position<(String, usize), String>,
+    },
+    ReturnSubmission {
+        id: u32,
+        status: submission::ReturnStatus,
+        note: String,
+    },
     GetIntro {
         id: Option<String>,
     },
@@ -189,8 +284,12 @@ pub async fn cmd(
     Json(body): Json<Body>,
 ) -> Result<Response, maud::Markup> {
     use html::cmd as cmd_html;
-    let mut index = state.index.lock().await;
-    let mut spotify_credentials = state.spotify_credentials.lock().await;
+    // Take the credentials first, in the same order as the music page.
+    let mut spotify_credentials = match &state.spotify_credentials {
+        Some(spotify_credentials) => Some(spotify_credentials.lock().await),
+        None => None,
+    };
+    let mut index = state.index.write().await;
 
     let user = get_cookie(&headers, "edat_user").ok_or(cmd_html::unauthorized())?;
 
@@ -198,10 +297,18 @@ pub async fn cmd(
         result.map_err(|err| match err {
             DataError::DuplicateId(id) => cmd_html::duplicate(id),
             DataError::MissingResource(kind, id) => cmd_html::missing(kind, id),
+            DataError::NotSubmitted(id, status) => cmd_html::not_submitted(id, status),
         })
     }
 
-    async fn refresh_spotify_data(index: &Index, spotify_credentials: &mut SpotifyCredentials) {
+    async fn refresh_spotify_data(
+        index: &Index,
+        spotify_credentials: Option<&mut SpotifyCredentials>,
+    ) {
+        // Without the music pages, there is nothing to refresh.
+        let Some(spotify_credentials) = spotify_credentials else {
+            return;
+        };
         SpotifyData::refresh_file(index, spotify_credentials.access_token().await.to_owned()).await;
     }
 
@@ -284,15 +391,16 @@ pub async fn cmd(
                 entry.parent_volume_part(),
             ),
             sections,
+            tags: entry.tags().join(" "),
         }
     }
 
     fn section_info(section: Section) -> cmd_html::SectionInfo {
         let in_entry = section.index_in_parent();
         let perspectives = section
-            .perspective_ids()
+            .perspectives()
             .iter()
-            .map(|s| s.to_string())
+            .map(|s| s.id().to_string())
             .collect::<Vec<_>>()
             .join(" ");
         let comments = section
@@ -316,10 +424,48 @@ pub async fn cmd(
             length: section.length(),
             status: format!("{:?}", section.status()),
             perspectives,
+            tags: section.tags().join(" "),
+            publish_at: section.publish_at().map(|t| t.timestamp()),
             comments,
         }
     }
 
+    fn revisions_info(section: Section) -> cmd_html::RevisionsInfo {
+        let revisions = section
+            .revisions()
+            .into_iter()
+            .rev()
+            .map(|r| cmd_html::SectionRevision {
+                number: r.number,
+                author: r
+                    .author
+                    .map_or_else(|| "unknown user".to_owned(), |u| u.id().to_owned()),
+                timestamp: r.timestamp,
+                added: r.added,
+                removed: r.removed,
+            })
+            .collect();
+        cmd_html::RevisionsInfo {
+            id: section.id(),
+            revisions,
+        }
+    }
+
+    fn trash(index: &Index) -> cmd_html::Trash {
+        let items = index
+            .trash()
+            .into_iter()
+            .rev()
+            .map(|t| cmd_html::TrashEntry {
+                kind: t.kind,
+                id: t.id,
+                parent: t.parent.unwrap_or_else(String::new),
+                removed: t.removed,
+            })
+            .collect();
+        cmd_html::Trash(items)
+    }
+
     fn volumes(index: &Index) -> cmd_html::Volumes {
         let volumes = index
             .volumes()
@@ -333,13 +479,56 @@ pub async fn cmd(
         cmd_html::Volumes(volumes)
     }
 
+    fn read_backup(config: &Config, archive: &str) -> Result<restore::Backup, maud::Markup> {
+        // Only archives and snapshots made by the website can be restored.
+        if archive.contains(['/', '\\']) || !archive.ends_with(".zip") {
+            return Err(cmd_html::missing("archive", archive.to_owned()));
+        }
+        let zip = fs::read(config.archive_dir.join(archive))
+            .or_else(|_| fs::read(config.snapshot_dir.join(archive)))
+            .map_err(|_| cmd_html::missing("archive", archive.to_owned()))?;
+        restore::Backup::read(&zip).map_err(|e| cmd_html::restore_error(archive, e))
+    }
+
     use Body as B;
     Ok(match body {
+        B::AddPerspective { id, perspective } => {
+            let mut section = map_err_html(index.section_mut(id))?;
+            map_err_html(section.add_perspective(perspective))?;
+            cmd_html::section(section_info(section.as_immut()))
+        }
         B::AddUserCode { id, code } => {
             let mut user = map_err_html(index.user_mut(id))?;
             user.add_code(code.to_lowercase());
             cmd_html::user(user_info(user.as_immut()))
         }
+        B::ApproveSubmission { id, position } => {
+            // Guest content only goes in featured volumes.
+            let volume = match &position {
+                Position::StartOf((volume, _)) | Position::EndOf((volume, _)) => volume.clone(),
+                Position::Before(sibling) | Position::After(sibling) => {
+                    let sibling = map_err_html(index.entry(sibling.clone()))?;
+                    sibling.parent_volume_id().to_owned()
+                }
+            };
+            let volume = map_err_html(index.volume(volume))?;
+            if volume.kind() != volume::Kind::Featured {
+                return Err(cmd_html::not_featured(volume.id()));
+            }
+            let entry = map_err_html(index.approve_submission(id, position))?;
+            cmd_html::entry(entry_info(entry.as_immut()))
+        }
+        B::Archives => {
+            let mut archives: Vec<_> = fs::read_dir(&state.config.archive_dir)
+                .map(|dir| {
+                    dir.filter_map(|f| f.ok()?.file_name().into_string().ok())
+                        .filter(|f| f.ends_with(".zip"))
+                        .collect()
+                })
+                .unwrap_or_default();
+            archives.sort();
+            cmd_html::archives(archives)
+        }
         B::DeleteEntry { id } => {
             let entry = map_err_html(index.entry_mut(id))?;
             let parent_volume = entry.parent_volume_id().to_owned();
@@ -359,6 +548,11 @@ pub async fn cmd(
             volume.remove();
             cmd_html::volumes(volumes(&index))
         }
+        B::EntryTags { id, tags } => {
+            let mut entry = map_err_html(index.entry_mut(id))?;
+            entry.set_tags(&tags);
+            cmd_html::entry(entry_info(entry.as_immut()))
+        }
         B::GetContent { id } => {
             let section = map_err_html(index.section(id))?;
             let content = section.content();
@@ -370,7 +564,7 @@ pub async fn cmd(
         }
         B::GetIntro { id } => match id {
             None => {
-                let content = fs::read_to_string("content/edat.intro").unwrap();
+                let content = index.storage().read_to_string("content/edat.intro").unwrap();
                 cmd_html::content("edat".to_owned(), content)
             }
             Some(id) => {
@@ -383,6 +577,10 @@ pub async fn cmd(
             let section = map_err_html(index.section(id))?;
             cmd_html::section(section_info(section))
         }
+        B::GetSubmission { id } => {
+            let submission = map_err_html(index.submission(id))?;
+            cmd_html::submission(submission)
+        }
         B::GetUser { id } => {
             let user = map_err_html(index.user(id))?;
             cmd_html::user(user_info(user))
@@ -404,6 +602,7 @@ pub async fn cmd(
                     "conversations-widget".to_owned(),
                     "random-widget".to_owned(),
                     "extras-widget".to_owned(),
+                    "notifications-widget".to_owned(),
                     "search-widget".to_owned(),
                 ]);
             }
@@ -432,19 +631,94 @@ pub async fn cmd(
         B::NewSection { date } => cmd_html::edit_section(None, &date),
         B::NewUser => cmd_html::edit_user(None),
         B::NewVolume => cmd_html::edit_volume(None),
+        B::RemovePerspective { id, perspective } => {
+            let mut section = map_err_html(index.section_mut(id))?;
+            map_err_html(section.remove_perspective(perspective))?;
+            cmd_html::section(section_info(section.as_immut()))
+        }
         B::RemoveUserCode { id, code } => {
             let mut user = map_err_html(index.user_mut(id))?;
             user.remove_code(&code.to_lowercase());
             cmd_html::user(user_info(user.as_immut()))
         }
+        B::PreviewRestore { archive, mode } => {
+            let backup = read_backup(&state.config, &archive)?;
+            let changes = backup
+                .changes(&index, mode)
+                .map_err(|e| cmd_html::restore_error(&archive, e))?;
+            cmd_html::restore_preview(&archive, mode, backup.problems(), changes)
+        }
+        B::PurgeTrash { days } => {
+            let cutoff = match days {
+                Some(days) => Some(Utc::now().timestamp() - days as i64 * 24 * 60 * 60),
+                None => state.config.trash_cutoff(),
+            };
+            let cutoff = cutoff.ok_or_else(cmd_html::no_retention)?;
+            let count = index.purge_trash(cutoff);
+            cmd_html::purged(count)
+        }
+        B::Restore { archive, mode } => {
+            let backup = read_backup(&state.config, &archive)?;
+            let changes = backup
+                .restore(&mut index, mode)
+                .map_err(|e| cmd_html::restore_error(&archive, e))?;
+            cmd_html::restored(&archive, changes)
+        }
+        B::RestoreEntry { id, position } => {
+            let entry = map_err_html(index.restore_entry(id, position))?;
+            cmd_html::entry(entry_info(entry.as_immut()))
+        }
+        B::RestoreSection { id, position } => {
+            let section = map_err_html(index.restore_section(id, position))?;
+            cmd_html::section(section_info(section.as_immut()))
+        }
+        B::RestoreVolume { id, position } => {
+            let volume = map_err_html(index.restore_volume(id, position))?;
+            cmd_html::volume(volume_info(volume.as_immut()))
+        }
+        B::RestoreRevision { id, revision } => {
+            let mut section = map_err_html(index.section_mut(id))?;
+            map_err_html(section.restore_revision(revision, user.to_owned()))?;
+            // Save the new revision before listing it.
+            drop(section);
+            cmd_html::revisions(revisions_info(index.section(id).unwrap()))
+        }
+        B::ReturnSubmission { id, status, note } => {
+            let submission = map_err_html(index.return_submission(id, status, &note))?;
+            cmd_html::submission(submission)
+        }
+        B::RevisionDiff { id, from, to } => {
+            let section = map_err_html(index.section(id))?;
+            let old = map_err_html(section.revision_content(from))?;
+            let new = map_err_html(section.revision_content(to))?;
+            let lines = revision::line_diff(&old, &new);
+            cmd_html::revision_diff(id, from, to, lines)
+        }
+        B::Revisions { id } => {
+            let section = map_err_html(index.section(id))?;
+            cmd_html::revisions(revisions_info(section))
+        }
+        B::ScheduleSection { id, publish_at } => {
+            let mut section = map_err_html(index.section_mut(id))?;
+            let publish_at = publish_at.and_then(|t| DateTime::from_timestamp(t, 0));
+            if !section.schedule(publish_at) {
+                return Err(cmd_html::already_complete(id));
+            }
+            cmd_html::section(section_info(section.as_immut()))
+        }
         B::SectionStatus { id, status } => {
             let mut section = map_err_html(index.section_mut(id))?;
             section.set_status(status);
             cmd_html::section(section_info(section.as_immut()))
         }
+        B::SectionTags { id, tags } => {
+            let mut section = map_err_html(index.section_mut(id))?;
+            section.set_tags(&tags);
+            cmd_html::section(section_info(section.as_immut()))
+        }
         B::SetContent { id, content } => {
             let mut section = map_err_html(index.section_mut(id))?;
-            section.set_content(&content);
+            section.set_content(&content, user.to_owned());
             cmd_html::section(section_info(section.as_immut()))
         }
         B::SetEntry {
@@ -465,7 +739,10 @@ pub async fn cmd(
                 volume.set_intro(&content);
                 cmd_html::volume(volume_info(volume.as_immut()))
             } else {
-                fs::write("content/edat.intro", &content).unwrap();
+                index
+                    .storage()
+                    .write("content/edat.intro", content.clone().into_bytes())
+                    .unwrap();
                 cmd_html::content("edat".to_owned(), content)
             }
         }
@@ -496,7 +773,7 @@ pub async fn cmd(
                     tracks_of_the_month: tracks,
                 });
             }
-            refresh_spotify_data(&index, &mut spotify_credentials).await;
+            refresh_spotify_data(&index, spotify_credentials.as_deref_mut()).await;
             index.save_index();
             cmd_html::ok()
         }
@@ -564,7 +841,7 @@ pub async fn cmd(
                     ratings: vec![rating],
                 });
             }
-            refresh_spotify_data(&index, &mut spotify_credentials).await;
+            refresh_spotify_data(&index, spotify_credentials.as_deref_mut()).await;
             index.save_index();
             cmd_html::ok()
         }
@@ -632,7 +909,7 @@ pub async fn cmd(
                     score,
                 });
             }
-            refresh_spotify_data(&index, &mut spotify_credentials).await;
+            refresh_spotify_data(&index, spotify_credentials.as_deref_mut()).await;
             index.save_index();
             cmd_html::ok()
         }
@@ -655,6 +932,32 @@ pub async fn cmd(
             volume.set_subtitle((!subtitle.is_empty()).then_some(&subtitle));
             cmd_html::volume(volume_info(volume.as_immut()))
         }
+        B::Submissions => {
+            // The owner only sees what has been sent in, with anything
+            // awaiting review first.
+            let mut submissions: Vec<_> = index
+                .submissions()
+                .into_iter()
+                .filter(|s| s.status != submission::Status::Draft)
+                .collect();
+            submissions.sort_by_key(|s| s.status != submission::Status::Submitted);
+            cmd_html::submissions(submissions)
+        }
+        B::Trash => cmd_html::trash(trash(&index)),
+        B::Snapshots => {
+            let snapshots =
+                snapshot::list(&state.config.snapshot_dir).map_err(cmd_html::snapshot_error)?;
+            cmd_html::snapshots(snapshots)
+        }
+        B::Tags => cmd_html::tags(index.tags()),
+        B::TakeSnapshot => {
+            let dir = &state.config.snapshot_dir;
+            let (snapshot, new) =
+                snapshot::take(index.storage(), dir).map_err(cmd_html::snapshot_error)?;
+            snapshot::rotate(dir, &state.config.snapshot_retention)
+                .map_err(cmd_html::snapshot_error)?;
+            cmd_html::snapshot_taken(snapshot, new)
+        }
         B::UserPrivilege { id, privilege } => {
             let mut user = map_err_html(index.user_mut(id))?;
             user.set_privilege(privilege);
@@ -6,7 +6,7 @@ pub async fn library_search(
     ReqPath(query): ReqPath<String>,
     State(state): State<AppState>,
 ) -> Markup {
-    let index = state.index.lock().await;
+    let index = state.index.read().await;
     let words: Vec<_> = query.split(",").collect();
     html::components::library_search(&index, &words)
 }
@@ -16,7 +16,7 @@ pub async fn thread(
     ReqPath((section, line)): ReqPath<(u32, usize)>,
     State(state): State<AppState>,
 ) -> Result<Markup, Markup> {
-    let index = state.index.lock().await;
+    let index = state.index.read().await;
     let user = auth::get_user(&headers, &index, None, false)?;
     Ok(html::components::thread(&user, section, line))
 }
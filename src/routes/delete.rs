@@ -4,7 +4,7 @@ pub async fn comment(
     State(state): State<AppState>,
     ReqPath((section, uuid)): ReqPath<(u32, u128)>,
 ) -> StatusCode {
-    let mut index = state.index.lock().await;
+    let mut index = state.index.write().await;
     
     let Ok(mut section) = index.section_mut(section) else {
         return StatusCode::NOT_FOUND;
@@ -14,3 +14,42 @@ pub async fn comment(
 
     StatusCode::OK
 }
+
+pub async fn feed_token(headers: HeaderMap, State(state): State<AppState>) -> StatusCode {
+    let user_id = {
+        let index = state.index.read().await;
+        let Ok(user) = auth::get_user(&headers, &index, None, false) else {
+            return StatusCode::UNAUTHORIZED;
+        };
+        user.id().to_owned()
+    };
+
+    let mut index = state.index.write().await;
+    let Ok(mut user) = index.user_mut(user_id) else {
+        return StatusCode::UNAUTHORIZED;
+    };
+
+    user.revoke_feed_token();
+
+    StatusCode::OK
+}
+
+pub async fn submission(
+    headers: HeaderMap,
+    State(state): State<AppState>,
+    ReqPath(id): ReqPath<u32>,
+) -> StatusCode {
+    let user_id = {
+        let index = state.index.read().await;
+        let Ok(user) = auth::get_user(&headers, &index, None, false) else {
+            return StatusCode::UNAUTHORIZED;
+        };
+        user.id().to_owned()
+    };
+
+    let mut index = state.index.write().await;
+    match index.remove_submission(id, &user_id) {
+        Ok(()) => StatusCode::OK,
+        Err(_) => StatusCode::NOT_FOUND,
+    }
+}
@@ -1,12 +1,15 @@
 use std::{
-    fs::{self, File},
-    io::{self},
-    path::{Path, PathBuf},
+    fs,
+    io::{Cursor, Write},
+    path::Path,
 };
 
+use axum::response::Html;
 use chrono::{Datelike, Utc};
 use zip::{write::SimpleFileOptions, ZipWriter};
 
+use crate::{book, data::storage::DATA_DIRS, feed};
+
 use super::*;
 
 pub async fn asset(ReqPath(file_name): ReqPath<String>) -> impl IntoResponse {
@@ -21,67 +24,144 @@ pub async fn style(ReqPath(file_name): ReqPath<String>) -> impl IntoResponse {
     static_file(format!("static/styles/{}", file_name), "text/css")
 }
 
-pub async fn image(ReqPath(file_name): ReqPath<String>) -> impl IntoResponse {
-    static_file(format!("content/images/{}", file_name), "image/jpeg")
+pub async fn image(
+    State(state): State<AppState>,
+    ReqPath(file_name): ReqPath<String>,
+) -> impl IntoResponse {
+    let index = state.index.read().await;
+    let path = format!("content/images/{}", file_name);
+    match index.storage().read(&path) {
+        Ok(content) => file_response(&file_name, content, "image/jpeg"),
+        Err(e) => {
+            println!("error reading file {path}: {e}");
+            StatusCode::NOT_FOUND.into_response()
+        }
+    }
+}
+
+pub async fn epub(
+    headers: HeaderMap,
+    State(state): State<AppState>,
+    ReqPath(id): ReqPath<String>,
+) -> Response {
+    let index = state.index.read().await;
+    if auth::get_user(&headers, &index, None, false).is_err() {
+        return StatusCode::UNAUTHORIZED.into_response();
+    }
+    let Ok(volume) = index.volume(id) else {
+        return StatusCode::NOT_FOUND.into_response();
+    };
+
+    let file_name = format!("{}.epub", volume.id());
+    file_response(
+        &file_name,
+        book::epub::volume(&volume),
+        "application/epub+zip",
+    )
 }
 
-pub async fn preview() -> impl IntoResponse {
-    static_file("content/preview.png".to_owned(), "image/png")
+pub async fn feed(
+    headers: HeaderMap,
+    State(state): State<AppState>,
+    ReqPath(token): ReqPath<String>,
+) -> Response {
+    let index = state.index.read().await;
+    let Some(user) = index.user_by_feed_token(&token) else {
+        return StatusCode::NOT_FOUND.into_response();
+    };
+
+    let body = feed::atom(&user, &site_url(&state.config, &headers));
+    (
+        [
+            (header::CONTENT_TYPE, "application/atom+xml; charset=utf-8"),
+            (header::CACHE_CONTROL, "no-store"),
+        ],
+        body,
+    )
+        .into_response()
 }
 
-pub async fn archive() -> impl IntoResponse {
+pub async fn print_volume(
+    headers: HeaderMap,
+    State(state): State<AppState>,
+    ReqPath(id): ReqPath<String>,
+) -> Response {
+    let index = state.index.read().await;
+    if auth::get_user(&headers, &index, None, false).is_err() {
+        return StatusCode::UNAUTHORIZED.into_response();
+    }
+    let Ok(volume) = index.volume(id) else {
+        return StatusCode::NOT_FOUND.into_response();
+    };
+
+    Html(book::print::volume(&volume)).into_response()
+}
+
+pub async fn print_entry(
+    headers: HeaderMap,
+    State(state): State<AppState>,
+    ReqPath(id): ReqPath<String>,
+) -> Response {
+    let index = state.index.read().await;
+    if auth::get_user(&headers, &index, None, false).is_err() {
+        return StatusCode::UNAUTHORIZED.into_response();
+    }
+    let Ok(entry) = index.entry(id) else {
+        return StatusCode::NOT_FOUND.into_response();
+    };
+
+    Html(book::print::entry(&entry)).into_response()
+}
+
+pub async fn preview(State(state): State<AppState>) -> impl IntoResponse {
+    let _preview_image = state.preview_image.lock().await;
+    static_file(&state.config.preview_image, "image/png")
+}
+
+pub async fn archive(State(state): State<AppState>) -> impl IntoResponse {
+    let index = state.index.read().await;
     let now = Utc::now();
-    let archive_path = format!("edat-{}-{}-{}.zip", now.year(), now.month(), now.day());
-    {
-        let archive_file = File::create(&archive_path).unwrap();
-        let mut zip = ZipWriter::new(archive_file);
-    
-        fn directory(path: PathBuf, zip: &mut ZipWriter<File>) {
-            let options = SimpleFileOptions::default();
-            for entry in fs::read_dir(path).unwrap() {
-                let entry = entry.unwrap();
-                let path = entry.path();
-                if path.is_dir() {
-                    directory(path, zip);
-                } else {
-                    zip.start_file(path.to_str().unwrap(), options).unwrap();
-                    let mut file = File::open(path).unwrap();
-                    io::copy(&mut file, zip).unwrap();
-                }
-            }
+    let archive_name = format!("edat-{}-{}-{}.zip", now.year(), now.month(), now.day());
+
+    let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+    let options = SimpleFileOptions::default();
+    for dir in DATA_DIRS {
+        for path in index.storage().list(dir).unwrap() {
+            zip.start_file(path.as_str(), options).unwrap();
+            zip.write_all(&index.storage().read(&path).unwrap())
+                .unwrap();
         }
-    
-        directory("content".into(), &mut zip);
-        directory("users".into(), &mut zip);
-        directory("archived".into(), &mut zip);
-        zip.finish().unwrap();
     }
+    let archive = zip.finish().unwrap().into_inner();
 
-    let response = static_file(archive_path.clone(), "application/zip");
-    fs::remove_file(archive_path).unwrap();
-    response
+    file_response(&archive_name, archive, "application/zip")
 }
 
-fn static_file(path: String, content_type: &'static str) -> Response {
-    let path = Path::new(&path);
+fn static_file(path: impl AsRef<Path>, content_type: &'static str) -> Response {
+    let path = path.as_ref();
     match fs::read(path) {
-        Ok(content) => (
-            [
-                (header::CONTENT_TYPE, content_type),
-                (
-                    header::CONTENT_DISPOSITION,
-                    &format!(
-                        "inline; filename=\"{}\"",
-                        path.file_name().unwrap().to_string_lossy()
-                    ),
-                ),
-            ],
+        Ok(content) => file_response(
+            &path.file_name().unwrap().to_string_lossy(),
             content,
-        )
-            .into_response(),
+            content_type,
+        ),
         Err(e) => {
-            println!("error reading file {}: {e}", path.to_str().unwrap());
+            println!("error reading file {}: {e}", path.display());
             StatusCode::NOT_FOUND.into_response()
         }
     }
 }
+
+fn file_response(file_name: &str, content: Vec<u8>, content_type: &'static str) -> Response {
+    (
+        [
+            (header::CONTENT_TYPE, content_type),
+            (
+                header::CONTENT_DISPOSITION,
+                &format!("inline; filename=\"{file_name}\""),
+            ),
+        ],
+        content,
+    )
+        .into_response()
+}
@@ -6,10 +6,12 @@ use axum::Json;
 
 use maud::Markup;
 
+use crate::config::Config;
 use crate::data::{user::Privilege as UserPrivilege, *};
 use crate::html;
 use crate::AppState;
 
+pub mod api;
 pub mod auth;
 pub mod cmd;
 pub mod components;
@@ -27,3 +29,19 @@ pub fn get_cookie<'a>(headers: &'a HeaderMap, key: &str) -> Option<&'a str> {
 pub fn no_cache(body: Markup) -> Response {
     ([(header::CACHE_CONTROL, "no-store")], body).into_response()
 }
+
+/// The address of the website, for links that are followed from elsewhere.
+pub fn site_url(config: &Config, headers: &HeaderMap) -> String {
+    if let Some(site_url) = &config.site_url {
+        return site_url.trim_end_matches('/').to_owned();
+    }
+    let host = headers
+        .get(header::HOST)
+        .and_then(|h| h.to_str().ok())
+        .unwrap_or("localhost");
+    let scheme = headers
+        .get("X-Forwarded-Proto")
+        .and_then(|p| p.to_str().ok())
+        .unwrap_or("http");
+    format!("{scheme}://{host}")
+}
@@ -13,14 +13,16 @@ pub async fn entry(
 ) -> Result<Response, Markup> {
     use html::pages::entry as entry_html;
 
-    let index = state.index.lock().await;
+    let index = state.index.read().await;
 
     let entry = match index.entry(entry.clone()) {
         Ok(entry) => entry,
         Err(_) => return Err(html::pages::entry::error(&headers, &entry)),
     };
 
-    image::entry_image(entry.title(), entry.summary());
+    let preview_image = state.preview_image.lock().await;
+    image::entry_image(entry.title(), entry.summary(), &state.config.preview_image);
+    drop(preview_image);
 
     let user = auth::get_user(&headers, &index, Some(entry.title().to_owned()), true)?;
 
@@ -45,7 +47,7 @@ pub async fn entry_by_section(
 ) -> Result<Response, Markup> {
     use html::pages::entry as entry_html;
 
-    let index = state.index.lock().await;
+    let index = state.index.read().await;
 
     let section = match index.section(section) {
         Ok(section) => section,
@@ -58,12 +60,15 @@ pub async fn entry_by_section(
         format!("Section {}", section.index_in_parent() + 1)
     };
 
+    let preview_image = state.preview_image.lock().await;
     image::section_image(
         section.parent_entry().title(),
         section.summary(),
         &section_index,
         &crate::data::date_string(&section.date()),
+        &state.config.preview_image,
     );
+    drop(preview_image);
 
     let user = auth::get_user(
         &headers,
@@ -90,9 +95,12 @@ pub async fn music(
     headers: HeaderMap,
     State(state): State<AppState>,
 ) -> Result<Response, Markup> {
-    let mut spotify_credentials = state.spotify_credentials.lock().await;
+    let Some(spotify_credentials) = &state.spotify_credentials else {
+        return Ok(StatusCode::NOT_FOUND.into_response());
+    };
+    let mut spotify_credentials = spotify_credentials.lock().await;
     let access_token = spotify_credentials.access_token().await;
-    let index = state.index.lock().await;
+    let index = state.index.read().await;
 
     Ok(no_cache(
         html::pages::music::music(&index, access_token, &headers).await,
@@ -104,9 +112,12 @@ pub async fn month_in_review(
     Path(month): Path<String>,
     State(state): State<AppState>,
 ) -> Result<Response, Markup> {
-    let mut spotify_credentials = state.spotify_credentials.lock().await;
+    let Some(spotify_credentials) = &state.spotify_credentials else {
+        return Ok(StatusCode::NOT_FOUND.into_response());
+    };
+    let mut spotify_credentials = spotify_credentials.lock().await;
     let access_token = spotify_credentials.access_token().await;
-    let index = state.index.lock().await;
+    let index = state.index.read().await;
 
     Ok(no_cache(
         html::pages::music::month_in_review(&index, month, access_token, &headers).await,
@@ -118,9 +129,12 @@ pub async fn album_review(
     Path(album): Path<String>,
     State(state): State<AppState>,
 ) -> Result<Response, Markup> {
-    let mut spotify_credentials = state.spotify_credentials.lock().await;
+    let Some(spotify_credentials) = &state.spotify_credentials else {
+        return Ok(StatusCode::NOT_FOUND.into_response());
+    };
+    let mut spotify_credentials = spotify_credentials.lock().await;
     let access_token = spotify_credentials.access_token().await;
-    let index = state.index.lock().await;
+    let index = state.index.read().await;
 
     Ok(no_cache(
         html::pages::music::album_review(&index, album, access_token, &headers).await,
@@ -131,24 +145,36 @@ pub async fn history(
     headers: HeaderMap,
     State(state): State<AppState>,
 ) -> Result<Response, Markup> {
-    let index = state.index.lock().await;
+    let index = state.index.read().await;
     let user = auth::get_user(&headers, &index, Some("Upload history".to_owned()), false)?;
 
     Ok(no_cache(html::pages::history::history(&headers, &user)))
 }
 
 pub async fn home(headers: HeaderMap, State(state): State<AppState>) -> Result<Response, Markup> {
-    let index = state.index.lock().await;
+    let index = state.index.read().await;
     let user = auth::get_user(&headers, &index, Some("Home".to_owned()), false)?;
 
     Ok(no_cache(html::pages::home::home(&headers, &user)))
 }
 
+pub async fn notifications(
+    headers: HeaderMap,
+    State(state): State<AppState>,
+) -> Result<Response, Markup> {
+    let index = state.index.read().await;
+    let user = auth::get_user(&headers, &index, Some("Notifications".to_owned()), false)?;
+
+    Ok(no_cache(html::pages::notifications::notifications(
+        &headers, &user,
+    )))
+}
+
 pub async fn profile(
     headers: HeaderMap,
     State(state): State<AppState>,
 ) -> Result<Response, Markup> {
-    let index = state.index.lock().await;
+    let index = state.index.read().await;
     let user = auth::get_user(&headers, &index, Some("Profile".to_owned()), false)?;
 
     Ok(no_cache(html::pages::profile::profile(&headers, &user)))
@@ -159,7 +185,7 @@ pub async fn search(
     State(state): State<AppState>,
     ReqPath(searches): ReqPath<String>,
 ) -> Result<Response, Markup> {
-    let index = state.index.lock().await;
+    let index = state.index.read().await;
     let _ = auth::get_user(&headers, &index, Some("Search".to_owned()), false)?;
 
     let searches: Vec<_> = searches.split(",").filter(|s| !s.is_empty()).collect();
@@ -173,14 +199,56 @@ pub async fn search_empty(
     headers: HeaderMap,
     State(state): State<AppState>,
 ) -> Result<Response, Markup> {
-    let index = state.index.lock().await;
+    let index = state.index.read().await;
     let _ = auth::get_user(&headers, &index, None, false)?;
 
     Ok(no_cache(html::pages::search::search(&headers, &index, &[])))
 }
 
+pub async fn new_submission(
+    headers: HeaderMap,
+    State(state): State<AppState>,
+) -> Result<Response, Markup> {
+    let index = state.index.read().await;
+    let _ = auth::get_user(&headers, &index, Some("Contribute".to_owned()), false)?;
+
+    Ok(no_cache(html::pages::submit::submit(&headers, None)))
+}
+
+pub async fn submission(
+    headers: HeaderMap,
+    State(state): State<AppState>,
+    ReqPath(id): ReqPath<u32>,
+) -> Result<Response, Markup> {
+    let index = state.index.read().await;
+    let user = auth::get_user(&headers, &index, Some("Contribute".to_owned()), false)?;
+
+    // Members only see their own submissions.
+    let submission = match index.submission(id) {
+        Ok(submission) if submission.author == user.id() => submission,
+        _ => return Err(html::pages::submit::error(&headers, id)),
+    };
+
+    Ok(no_cache(html::pages::submit::submit(
+        &headers,
+        Some(&submission),
+    )))
+}
+
+pub async fn tag(
+    headers: HeaderMap,
+    State(state): State<AppState>,
+    ReqPath(tag): ReqPath<String>,
+) -> Result<Response, Markup> {
+    let index = state.index.read().await;
+    let tag = tag.to_lowercase();
+    let user = auth::get_user(&headers, &index, Some(format!("#{tag}")), false)?;
+
+    Ok(no_cache(html::pages::tag::tag(&headers, &user, &tag)))
+}
+
 pub async fn terminal(headers: HeaderMap, State(state): State<AppState>) -> Result<Markup, Markup> {
-    let index = state.index.lock().await;
+    let index = state.index.read().await;
     let user = auth::get_user(&headers, &index, Some("Terminal".to_owned()), false)?;
     Ok(html::pages::terminal(
         &headers,
@@ -188,12 +256,25 @@ pub async fn terminal(headers: HeaderMap, State(state): State<AppState>) -> Resu
     ))
 }
 
+pub async fn unsubscribe(
+    headers: HeaderMap,
+    State(state): State<AppState>,
+    ReqPath(token): ReqPath<String>,
+) -> Response {
+    let index = state.index.read().await;
+    let Some(user) = index.user_by_digest_token(&token) else {
+        return StatusCode::NOT_FOUND.into_response();
+    };
+
+    no_cache(html::pages::unsubscribe(&headers, &user, false))
+}
+
 pub async fn volume(
     headers: HeaderMap,
     State(state): State<AppState>,
     ReqPath(volume): ReqPath<String>,
 ) -> Result<Response, Markup> {
-    let index = state.index.lock().await;
+    let index = state.index.read().await;
 
     let volume = match index.volume(volume.clone()) {
         Ok(volume) => volume,
@@ -211,7 +292,7 @@ pub async fn volumes(
     headers: HeaderMap,
     State(state): State<AppState>,
 ) -> Result<Response, Markup> {
-    let index = state.index.lock().await;
+    let index = state.index.read().await;
     auth::get_user(&headers, &index, Some("The library".to_owned()), false)?;
     Ok(no_cache(html::pages::volume::library(&headers, &index)))
 }
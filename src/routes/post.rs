@@ -6,13 +6,15 @@ pub async fn comment(
     ReqPath((section, line)): ReqPath<(u32, usize)>,
     body: String,
 ) -> StatusCode {
-    let mut index = state.index.lock().await;
-
-    let Ok(user) = auth::get_user(&headers, &index, None, false) else {
-        return StatusCode::INTERNAL_SERVER_ERROR;
+    let author = {
+        let index = state.index.read().await;
+        let Ok(user) = auth::get_user(&headers, &index, None, false) else {
+            return StatusCode::INTERNAL_SERVER_ERROR;
+        };
+        user.id().to_owned()
     };
-    let author = user.id().to_owned();
 
+    let mut index = state.index.write().await;
     let Ok(mut section) = index.section_mut(section) else {
         return StatusCode::NOT_FOUND;
     };
@@ -22,18 +24,98 @@ pub async fn comment(
     StatusCode::OK
 }
 
+pub async fn reply(
+    headers: HeaderMap,
+    State(state): State<AppState>,
+    ReqPath((section, uuid)): ReqPath<(u32, u128)>,
+    body: String,
+) -> StatusCode {
+    let author = {
+        let index = state.index.read().await;
+        let Ok(user) = auth::get_user(&headers, &index, None, false) else {
+            return StatusCode::INTERNAL_SERVER_ERROR;
+        };
+        user.id().to_owned()
+    };
+
+    let mut index = state.index.write().await;
+    let Ok(mut section) = index.section_mut(section) else {
+        return StatusCode::NOT_FOUND;
+    };
+
+    if !section.add_reply(author, uuid, &body) {
+        return StatusCode::NOT_FOUND;
+    }
+
+    StatusCode::OK
+}
+
+pub async fn react_line(
+    headers: HeaderMap,
+    State(state): State<AppState>,
+    ReqPath((section, line, reaction)): ReqPath<(u32, usize, Reaction)>,
+) -> StatusCode {
+    react(headers, state, section, Target::Line(line), reaction).await
+}
+
+pub async fn react_comment(
+    headers: HeaderMap,
+    State(state): State<AppState>,
+    ReqPath((section, uuid, reaction)): ReqPath<(u32, u128, Reaction)>,
+) -> StatusCode {
+    react(headers, state, section, Target::Comment(uuid), reaction).await
+}
+
+async fn react(
+    headers: HeaderMap,
+    state: AppState,
+    section: u32,
+    target: Target,
+    reaction: Reaction,
+) -> StatusCode {
+    let user = {
+        let index = state.index.read().await;
+        let Ok(user) = auth::get_user(&headers, &index, None, false) else {
+            return StatusCode::UNAUTHORIZED;
+        };
+        user.id().to_owned()
+    };
+
+    let mut index = state.index.write().await;
+    let Ok(mut section) = index.section_mut(section) else {
+        return StatusCode::NOT_FOUND;
+    };
+
+    if !section.toggle_reaction(user, target, reaction) {
+        return StatusCode::NOT_FOUND;
+    }
+
+    StatusCode::OK
+}
+
 pub async fn edit_comment(
+    headers: HeaderMap,
     State(state): State<AppState>,
     ReqPath((section, uuid)): ReqPath<(u32, u128)>,
     body: String,
 ) -> StatusCode {
-    let mut index = state.index.lock().await;
-    
+    let user = {
+        let index = state.index.read().await;
+        let Ok(user) = auth::get_user(&headers, &index, None, false) else {
+            return StatusCode::UNAUTHORIZED;
+        };
+        user.id().to_owned()
+    };
+
+    let mut index = state.index.write().await;
     let Ok(mut section) = index.section_mut(section) else {
         return StatusCode::NOT_FOUND;
     };
 
-    section.edit_comment(uuid, &body);
+    // Only the author can edit a comment.
+    if !section.edit_comment(&user, uuid, &body) {
+        return StatusCode::NOT_FOUND;
+    }
 
     StatusCode::OK
 }
@@ -42,7 +124,7 @@ pub async fn unremove_comment(
     State(state): State<AppState>,
     ReqPath((section, uuid)): ReqPath<(u32, u128)>,
 ) -> StatusCode {
-    let mut index = state.index.lock().await;
+    let mut index = state.index.write().await;
     
     let Ok(mut section) = index.section_mut(section) else {
         return StatusCode::NOT_FOUND;
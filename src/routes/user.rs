@@ -15,31 +15,34 @@ pub async fn register(
     State(state): State<AppState>,
     Json(body): Json<RegisterBody>,
 ) {
-    let mut index = state.index.lock().await;
+    let (user_id, sections) = {
+        let index = state.index.read().await;
+        let Ok(user) = auth::get_user_uninit(&headers, &index, None, false) else {
+            return;
+        };
 
-    // Collect the sections the user has read.
-    let mut sections: Vec<u32> = Vec::new();
-    if body.entries.get(0).is_some_and(|e| e == "$all") {
-        // The user indicated they have read everything.
-        sections.extend(index.sections().map(|s| s.id()));
-    } else {
-        // Load the specific entries.
-        for entry in body.entries {
-            let Ok(entry) = index.entry(entry) else {
-                continue;
-            };
-            sections.extend(entry.section_ids());
+        // Collect the sections the user has read.
+        let mut sections: Vec<u32> = Vec::new();
+        if body.entries.first().is_some_and(|e| e == "$all") {
+            // The user indicated they have read everything.
+            sections.extend(index.sections().map(|s| s.id()));
+        } else {
+            // Load the specific entries.
+            for entry in body.entries {
+                let Ok(entry) = index.entry(entry) else {
+                    continue;
+                };
+                sections.extend(entry.section_ids());
+            }
         }
-    }
+        (user.id().to_owned(), sections)
+    };
 
     // Update the user's history and widget preferences.
-    let user_id = {
-        let Ok(user) = auth::get_user_uninit(&headers, &index, None, false) else {
-            return;
-        };
-        user.id().to_owned()
+    let mut index = state.index.write().await;
+    let Ok(mut user) = index.user_mut(user_id) else {
+        return;
     };
-    let mut user = index.user_mut(user_id).unwrap();
     for section in sections {
         user.finished_section(section);
     }
@@ -52,35 +55,114 @@ pub async fn set_widgets(
     State(state): State<AppState>,
     Json(widgets): Json<Vec<String>>,
 ) -> StatusCode {
-    let mut index = state.index.lock().await;
-
     let user_id = {
+        let index = state.index.read().await;
         let Ok(user) = auth::get_user(&headers, &index, None, false) else {
             return StatusCode::UNAUTHORIZED;
         };
         user.id().to_owned()
     };
-    let mut user = index.user_mut(user_id).unwrap();
+
+    let mut index = state.index.write().await;
+    let Ok(mut user) = index.user_mut(user_id) else {
+        return StatusCode::UNAUTHORIZED;
+    };
 
     user.set_widgets(widgets);
 
     StatusCode::OK
 }
 
+pub async fn reset_feed_token(
+    headers: HeaderMap,
+    State(state): State<AppState>,
+) -> Result<String, StatusCode> {
+    let user_id = {
+        let index = state.index.read().await;
+        let Ok(user) = auth::get_user(&headers, &index, None, false) else {
+            return Err(StatusCode::UNAUTHORIZED);
+        };
+        user.id().to_owned()
+    };
+
+    let mut index = state.index.write().await;
+    let Ok(mut user) = index.user_mut(user_id) else {
+        return Err(StatusCode::UNAUTHORIZED);
+    };
+
+    Ok(user.reset_feed_token())
+}
+
+pub async fn set_followed_volumes(
+    headers: HeaderMap,
+    State(state): State<AppState>,
+    Json(volumes): Json<Vec<String>>,
+) -> StatusCode {
+    let (user_id, volumes) = {
+        let index = state.index.read().await;
+        let Ok(user) = auth::get_user(&headers, &index, None, false) else {
+            return StatusCode::UNAUTHORIZED;
+        };
+        let mut ids = Vec::new();
+        for volume in volumes {
+            let Ok(volume) = index.volume(volume) else {
+                return StatusCode::NOT_FOUND;
+            };
+            ids.push(volume.id().to_owned());
+        }
+        (user.id().to_owned(), ids)
+    };
+
+    let mut index = state.index.write().await;
+    let Ok(mut user) = index.user_mut(user_id) else {
+        return StatusCode::UNAUTHORIZED;
+    };
+
+    user.set_followed_volumes(volumes);
+
+    StatusCode::OK
+}
+
+pub async fn read_notifications(
+    headers: HeaderMap,
+    State(state): State<AppState>,
+    Json(id): Json<Option<u32>>,
+) -> StatusCode {
+    let user_id = {
+        let index = state.index.read().await;
+        let Ok(user) = auth::get_user(&headers, &index, None, false) else {
+            return StatusCode::UNAUTHORIZED;
+        };
+        user.id().to_owned()
+    };
+
+    let mut index = state.index.write().await;
+    let Ok(mut user) = index.user_mut(user_id) else {
+        return StatusCode::UNAUTHORIZED;
+    };
+
+    user.read_notifications(id);
+
+    StatusCode::OK
+}
+
 pub async fn set_preferences(
     headers: HeaderMap,
     State(state): State<AppState>,
     Json(body): Json<HashMap<String, Option<String>>>,
 ) {
-    let mut index = state.index.lock().await;
-
     let user_id = {
+        let index = state.index.read().await;
         let Ok(user) = auth::get_user(&headers, &index, None, false) else {
             return;
         };
         user.id().to_owned()
     };
-    let mut user = index.user_mut(user_id).unwrap();
+
+    let mut index = state.index.write().await;
+    let Ok(mut user) = index.user_mut(user_id) else {
+        return;
+    };
 
     for (k, v) in body {
         match v {
@@ -94,6 +176,55 @@ pub async fn set_preferences(
     }
 }
 
+#[derive(Deserialize)]
+pub struct SubmissionBody {
+    id: Option<u32>,
+    draft: Draft,
+    submit: bool,
+}
+
+pub async fn unsubscribe(
+    headers: HeaderMap,
+    State(state): State<AppState>,
+    ReqPath(token): ReqPath<String>,
+) -> Response {
+    let mut index = state.index.write().await;
+    let Some(user_id) = index
+        .user_by_digest_token(&token)
+        .map(|u| u.id().to_owned())
+    else {
+        return StatusCode::NOT_FOUND.into_response();
+    };
+
+    let mut user = index.user_mut(user_id.clone()).unwrap();
+    user.remove_preference("digest");
+    drop(user);
+
+    let user = index.user(user_id).unwrap();
+    no_cache(html::pages::unsubscribe(&headers, &user, true))
+}
+
+pub async fn save_submission(
+    headers: HeaderMap,
+    State(state): State<AppState>,
+    Json(body): Json<SubmissionBody>,
+) -> Result<String, StatusCode> {
+    let user_id = {
+        let index = state.index.read().await;
+        let Ok(user) = auth::get_user(&headers, &index, None, false) else {
+            return Err(StatusCode::UNAUTHORIZED);
+        };
+        user.id().to_owned()
+    };
+
+    let mut index = state.index.write().await;
+    let Ok(submission) = index.save_submission(body.id, user_id, body.draft, body.submit) else {
+        return Err(StatusCode::NOT_FOUND);
+    };
+
+    Ok(submission.id.to_string())
+}
+
 #[derive(Deserialize)]
 pub struct ReadQuery {
     progress: Option<usize>,
@@ -106,15 +237,18 @@ pub async fn read(
     ReqPath(id): ReqPath<String>,
     Query(options): Query<ReadQuery>,
 ) -> StatusCode {
-    let mut index = state.index.lock().await;
-
     let user_id = {
+        let index = state.index.read().await;
         let Ok(user) = auth::get_user(&headers, &index, None, false) else {
             return StatusCode::UNAUTHORIZED;
         };
         user.id().to_owned()
     };
-    let mut user = index.user_mut(user_id).unwrap();
+
+    let mut index = state.index.write().await;
+    let Ok(mut user) = index.user_mut(user_id) else {
+        return StatusCode::UNAUTHORIZED;
+    };
 
     if options.entry.unwrap_or(false) {
         println!("Entry finished!");
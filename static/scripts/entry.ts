@@ -65,6 +65,7 @@ let openComment: HTMLElement | null = null;
 let touchTime = 0;
 
 let editUuid: string | null = null;
+let replyUuid: string | null = null;
 
 const textlines = document.getElementsByClassName("textline") as HTMLCollectionOf<HTMLElement>;
 
@@ -91,6 +92,8 @@ function showComments(lineElement: HTMLElement) {
     }
 
     openComment = lineElement;
+    editUuid = null;
+    replyUuid = null;
 
     const elThread = document.createElement("div");
     elThread.id = "thread";
@@ -117,9 +120,30 @@ function showComments(lineElement: HTMLElement) {
 
         const elCommentInstructions = elThread
             .querySelector("#comment-instructions") as HTMLElement;
+        const instructions = elCommentInstructions.innerText;
 
         (elThread.querySelector("#close-comments") as HTMLElement).onclick = hideComments;
 
+        updateLineReactions(lineElement, elThread);
+
+        for (const el of elThread.querySelectorAll(".reaction") as NodeListOf<HTMLElement>) {
+            el.onclick = () => {
+                const reaction = el.getAttribute("edat_reaction")!;
+                const elComment = el.closest(".comment[edat_uuid]") as HTMLElement | null;
+                const url = elComment
+                    ? `/react_comment/${sectionId}/${elComment.getAttribute("edat_uuid")}/${reaction}`
+                    : `/react/${sectionId}/${lineNumber}/${reaction}`;
+
+                hideComments();
+
+                fetch(url, {
+                    method: "POST",
+                }).then(() => {
+                    showComments(lineElement);
+                });
+            }
+        }
+
         for (const el of elThread.querySelectorAll(".remove") as NodeListOf<HTMLElement>) {
             el.onclick = () => {
                 const uuid = el.getAttribute("edat_uuid")!;
@@ -178,6 +202,28 @@ function showComments(lineElement: HTMLElement) {
             }
         }
 
+        for (const el of elThread.querySelectorAll(".reply") as NodeListOf<HTMLElement>) {
+            el.onclick = () => {
+                const myUuid = el.getAttribute("edat_uuid")!;
+
+                const elReplying = elThread.querySelector(".replying") as HTMLElement | null;
+                if (elReplying) {
+                    elReplying.classList.remove("replying");
+                }
+
+                if (replyUuid == myUuid) {
+                    replyUuid = null;
+                    elCommentInstructions.innerText = instructions;
+                    return;
+                }
+
+                replyUuid = myUuid;
+                el.closest(".comment")!.classList.add("replying");
+                elCommentInstructions.innerText = `Replying to ${el.getAttribute("edat_author")}…`;
+                elUserComment.focus();
+            }
+        }
+
         const elUserComment = document.getElementById("user-comment") as HTMLTextAreaElement;
         if (elUserComment) {
             elUserComment.onkeydown = ev => {
@@ -198,7 +244,10 @@ function showComments(lineElement: HTMLElement) {
                             showComments(lineElement);
                         });
                     } else {
-                        fetch(`/comment/${sectionId}/${lineNumber}`, {
+                        const url = replyUuid
+                            ? `/reply/${sectionId}/${replyUuid}`
+                            : `/comment/${sectionId}/${lineNumber}`;
+                        fetch(url, {
                             method: "POST",
                             body: elUserComment.value,
                         }).then(() => {
@@ -211,6 +260,25 @@ function showComments(lineElement: HTMLElement) {
     }));
 }
 
+// Show the line's reaction counts beside it, as they are in the thread.
+function updateLineReactions(lineElement: HTMLElement, elThread: HTMLElement) {
+    const counts = Array.from(
+        elThread.querySelectorAll("#line-reactions .reaction.used") as NodeListOf<HTMLElement>
+    ).map(el => " " + el.innerText.replace(" ", ""));
+
+    let elCounts = lineElement.querySelector(".line-reactions") as HTMLElement | null;
+    if (counts.length == 0) {
+        elCounts?.remove();
+        return;
+    }
+    if (!elCounts) {
+        elCounts = document.createElement("span");
+        elCounts.className = "line-reactions";
+        lineElement.appendChild(elCounts);
+    }
+    elCounts.innerText = counts.join("");
+}
+
 function hideComments() {
     const elThread = document.getElementById("thread") as HTMLElement;
     elThread.style.display = "none";
@@ -3,6 +3,20 @@ import "./standard.js";
 
 universal.processUtcs();
 
+// Opening a notification marks it read.
+for (const el of document.querySelectorAll("#notifications-widget .notification") as NodeListOf<HTMLAnchorElement>) {
+    el.addEventListener("click", () => {
+        fetch("/notifications-read", {
+            method: "POST",
+            body: el.dataset.id,
+            headers: {
+                "Content-Type": "application/json",
+            },
+            keepalive: true,
+        });
+    });
+}
+
 const elRecentExpand = document.getElementById("recent-expand") as HTMLButtonElement | null;
 const elRecentCarousel = document.getElementById("recent-carousel") as HTMLDivElement;
 if (elRecentExpand) {
@@ -0,0 +1,28 @@
+import * as universal from "./universal.js";
+import "./standard.js";
+
+universal.processUtcs();
+
+function markRead(id: number | null) {
+    return fetch("/notifications-read", {
+        method: "POST",
+        body: JSON.stringify(id),
+        headers: {
+            "Content-Type": "application/json",
+        },
+        keepalive: true,
+    });
+}
+
+for (const el of document.querySelectorAll(".notification.unread") as NodeListOf<HTMLAnchorElement>) {
+    el.addEventListener("click", () => {
+        markRead(Number.parseInt(el.dataset.id!));
+    });
+}
+
+const readAllButton = document.getElementById("read-all") as HTMLButtonElement | null;
+if (readAllButton) {
+    readAllButton.onclick = () => {
+        markRead(null).then(() => location.reload());
+    };
+}
@@ -71,6 +71,54 @@ historyExpandButton.onclick = () => {
     }
 };
 
+const elFeedLink = document.getElementById("feed-link") as HTMLInputElement | null;
+if (elFeedLink) {
+    elFeedLink.value = location.origin + elFeedLink.value;
+    elFeedLink.onclick = () => elFeedLink.select();
+}
+
+const feedButtons: [string, string][] = [
+    ["feed-on", "POST"],
+    ["feed-reset", "POST"],
+    ["feed-off", "DELETE"],
+];
+for (const [id, method] of feedButtons) {
+    const button = document.getElementById(id) as HTMLButtonElement | null;
+    if (button) {
+        button.onclick = () => {
+            fetch("/feed-token", { method }).then(() => location.reload());
+        };
+    }
+}
+
+const elFeedVolumes = document.getElementById("feed-volumes") as HTMLDivElement;
+elFeedVolumes.onchange = () => {
+    const checked = elFeedVolumes.querySelectorAll("input:checked") as NodeListOf<HTMLInputElement>;
+    fetch("/feed-volumes", {
+        method: "POST",
+        body: JSON.stringify(Array.from(checked).map(input => input.value)),
+        headers: {
+            "Content-Type": "application/json",
+        },
+    });
+};
+
+const elDigestEmail = document.getElementById("digest-email") as HTMLInputElement;
+const elDigestFrequency = document.getElementById("digest-frequency") as HTMLSelectElement;
+elDigestEmail.onchange = elDigestFrequency.onchange = () => {
+    const email = elDigestEmail.value.trim();
+    fetch("/preferences", {
+        method: "POST",
+        body: JSON.stringify({
+            email: email == "" ? null : email,
+            digest: elDigestFrequency.value == "" ? null : elDigestFrequency.value,
+        }),
+        headers: {
+            "Content-Type": "application/json",
+        },
+    });
+};
+
 const homeButton = document.getElementById("home") as HTMLButtonElement;
 homeButton.onclick = () => {
     if (document.referrer == "") {
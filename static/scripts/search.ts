@@ -78,7 +78,9 @@ function showBody(subresult: HTMLDivElement) {
     elBody.innerHTML = `
         <p class="loading">Loading results</p>
     `;
-    const words = searchInput.value.split(" ").filter(s => s.length > 0);
+    const words = searchInput.value
+        .split(" ")
+        .filter(s => s.length > 0 && !s.startsWith("tag:"));
     const search = words.join(",");
     fetch(`/search/${searchPath}/${search}`).then(res => res.text().then(html => {
         elBody.innerHTML = html;
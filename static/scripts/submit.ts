@@ -0,0 +1,74 @@
+import "./universal.js";
+import * as standard from "./standard.js";
+
+const elDraft = document.getElementById("draft") as HTMLDivElement;
+const elTitle = document.getElementById("draft-title") as HTMLInputElement | null;
+const elDescription = document.getElementById("draft-description") as HTMLTextAreaElement | null;
+const elSummary = document.getElementById("draft-summary") as HTMLTextAreaElement | null;
+const elContent = document.getElementById("draft-content") as HTMLTextAreaElement | null;
+
+let submissionId = elDraft.dataset.id ? Number.parseInt(elDraft.dataset.id) : null;
+
+function save(submit: boolean) {
+    fetch("/submission", {
+        method: "POST",
+        body: JSON.stringify({
+            id: submissionId,
+            draft: {
+                title: elTitle!.value,
+                description: elDescription!.value,
+                summary: elSummary!.value,
+                content: elContent!.value,
+            },
+            submit,
+        }),
+        headers: {
+            "Content-Type": "application/json",
+        },
+    }).then(res => {
+        if (!res.ok) {
+            standard.drawerNotification("Couldn’t save your draft", 3000, () => {});
+            return;
+        }
+        res.text().then(id => {
+            if (submit) {
+                location.replace("/profile");
+                return;
+            }
+            if (submissionId == null) {
+                submissionId = Number.parseInt(id);
+                history.replaceState(null, "", `/submit/${id}`);
+            }
+            standard.drawerNotification("Draft saved", 2000, () => {});
+        });
+    });
+}
+
+const saveButton = document.getElementById("save") as HTMLButtonElement | null;
+if (saveButton) {
+    saveButton.onclick = () => save(false);
+}
+
+const submitButton = document.getElementById("submit") as HTMLButtonElement | null;
+if (submitButton) {
+    submitButton.onclick = () => {
+        if (elTitle!.value.trim() == "" || elContent!.value.trim() == "") {
+            standard.drawerNotification("Give your entry a title and some text first", 3000, () => {});
+            return;
+        }
+        if (confirm("Submit this entry for review? You won’t be able to change it unless the editor asks for changes.")) {
+            save(true);
+        }
+    };
+}
+
+const withdrawButton = document.getElementById("withdraw") as HTMLButtonElement | null;
+if (withdrawButton) {
+    withdrawButton.onclick = () => {
+        if (confirm("Delete this entry? This can’t be undone.")) {
+            fetch(`/submission/${submissionId}`, {
+                method: "DELETE",
+            }).then(() => location.replace("/profile"));
+        }
+    };
+}
@@ -0,0 +1,2 @@
+import "./universal.js";
+import "./standard.js";
@@ -8,6 +8,7 @@ type Position<C, I> =
 
 type ContentStatus = "Missing" | "Incomplete" | "Complete";
 type UserPrivilege = "Owner" | "Reader";
+type RestoreMode = "Replace" | "Merge";
 
 type ContentType =
     | "Journal"
@@ -19,6 +20,7 @@ type ContentType =
 
 const contentStatuses: ContentStatus[] = ["Missing", "Incomplete", "Complete"];
 const userPrivileges: UserPrivilege[] = ["Owner", "Reader"];
+const restoreModes: RestoreMode[] = ["Replace", "Merge"];
 const contentTypes: ContentType[] = [
     "Journal",
     "Archive",
@@ -78,6 +80,30 @@ type Cmd =
             status: ContentStatus,
         }
     }
+    | {
+        ScheduleSection: {
+            id: number,
+            publish_at: number | null,
+        }
+    }
+    | {
+        SectionTags: {
+            id: number,
+            tags: string[],
+        }
+    }
+    | {
+        AddPerspective: {
+            id: number,
+            perspective: number,
+        }
+    }
+    | {
+        RemovePerspective: {
+            id: number,
+            perspective: number,
+        }
+    }
     | {
         GetEntry: {
             id: string,
@@ -111,6 +137,12 @@ type Cmd =
             position: Position<[string, number], string>,
         }
     }
+    | {
+        EntryTags: {
+            id: string,
+            tags: string[],
+        }
+    }
     | {
         GetVolume: {
             id: string,
@@ -186,6 +218,7 @@ type Cmd =
         },
     }
     | "Volumes"
+    | "Tags"
     | "NextSectionId"
     | "Images"
     | {
@@ -210,6 +243,82 @@ type Cmd =
             content: string,
         }
     }
+    | "Trash"
+    | {
+        RestoreVolume: {
+            id: string,
+            position: Position<null, string> | null,
+        }
+    }
+    | {
+        RestoreEntry: {
+            id: string,
+            position: Position<[string, number], string> | null,
+        }
+    }
+    | {
+        RestoreSection: {
+            id: number,
+            position: Position<string, number> | null,
+        }
+    }
+    | {
+        PurgeTrash: {
+            days: number | null,
+        }
+    }
+    | "Archives"
+    | {
+        PreviewRestore: {
+            archive: string,
+            mode: RestoreMode,
+        }
+    }
+    | {
+        Restore: {
+            archive: string,
+            mode: RestoreMode,
+        }
+    }
+    | "Snapshots"
+    | "TakeSnapshot"
+    | {
+        Revisions: {
+            id: number,
+        }
+    }
+    | {
+        RevisionDiff: {
+            id: number,
+            from: number,
+            to: number,
+        }
+    }
+    | {
+        RestoreRevision: {
+            id: number,
+            revision: number,
+        }
+    }
+    | "Submissions"
+    | {
+        GetSubmission: {
+            id: number,
+        }
+    }
+    | {
+        ApproveSubmission: {
+            id: number,
+            position: Position<[string, number], string>,
+        }
+    }
+    | {
+        ReturnSubmission: {
+            id: number,
+            status: "Rejected" | "ChangesRequested",
+            note: string,
+        }
+    }
     | {
         InitUser: {
             id: string,
@@ -309,6 +418,97 @@ function parseCommand(command: string) {
                 status,
             },
         });
+    } else if (root == "schedule") {
+        if (!expectArgs(3)) {
+            return;
+        }
+        const section = Number.parseInt(args[1]);
+        if (!isNumber(section)) {
+            parseError();
+            return;
+        }
+        let publishAt: number | null = null;
+        if (args[2] != "clear") {
+            // The time is read in the owner's own timezone.
+            const time = new Date(`${args[2]}T${args[3] ?? "00:00"}`).getTime();
+            if (!isNumber(time)) {
+                parseError();
+                return;
+            }
+            publishAt = Math.floor(time / 1000);
+        }
+        submitAction = updateSection(section);
+        cmd({
+            ScheduleSection: {
+                id: section,
+                publish_at: publishAt,
+            },
+        });
+    } else if (root == "tag") {
+        if (!expectArgs(3)) {
+            return;
+        }
+        const id = args[2];
+        const tags = args.slice(3);
+        switch (args[1]) {
+            case "section":
+                {
+                    const section = Number.parseInt(id);
+                    if (!isNumber(section)) {
+                        parseError();
+                        return;
+                    }
+                    submitAction = updateSection(section);
+                    cmd({
+                        SectionTags: {
+                            id: section,
+                            tags,
+                        },
+                    });
+                    break;
+                }
+            case "entry":
+                submitAction = updateEntry(id);
+                cmd({
+                    EntryTags: {
+                        id,
+                        tags,
+                    },
+                });
+                break;
+            default:
+                parseError();
+        }
+    } else if (root == "perspective") {
+        if (!expectArgs(4)) {
+            return;
+        }
+        const section = Number.parseInt(args[2]);
+        const perspective = Number.parseInt(args[3]);
+        if (!isNumber(section) || !isNumber(perspective)) {
+            parseError();
+            return;
+        }
+        submitAction = updateSection(section);
+        if (args[1] == "add") {
+            cmd({
+                AddPerspective: {
+                    id: section,
+                    perspective,
+                },
+            });
+        } else if (args[1] == "remove") {
+            cmd({
+                RemovePerspective: {
+                    id: section,
+                    perspective,
+                },
+            });
+        } else {
+            parseError();
+        }
+    } else if (root == "tags") {
+        cmd("Tags");
     } else if (root == "volumetype") {
         if (!expectArgs(3)) {
             return;
@@ -591,6 +791,214 @@ function parseCommand(command: string) {
                 id: section,
             },
         });
+    } else if (root == "trash") {
+        if (args.length == 1) {
+            cmd("Trash");
+        } else if (args[1] == "restore") {
+            if (!expectArgs(4)) {
+                return;
+            }
+            const id = args[3];
+            const positionArgs = args.slice(4);
+            switch (args[2]) {
+                case "section":
+                    {
+                        const section = Number.parseInt(id);
+                        const position = parseSectionPosition(positionArgs);
+                        if (!isNumber(section) || (positionArgs.length > 0 && position[0] == null)) {
+                            parseError();
+                            return;
+                        }
+                        submitAction = updateSection(section);
+                        cmd({
+                            RestoreSection: {
+                                id: section,
+                                position: position[0],
+                            }
+                        });
+                        break;
+                    }
+                case "entry":
+                    {
+                        const position = parseEntryPosition(positionArgs);
+                        if (positionArgs.length > 0 && position[0] == null) {
+                            parseError();
+                            return;
+                        }
+                        submitAction = updateEntry(id);
+                        cmd({
+                            RestoreEntry: {
+                                id,
+                                position: position[0],
+                            }
+                        });
+                        break;
+                    }
+                case "volume":
+                    {
+                        const position = parseVolumePosition(positionArgs);
+                        if (positionArgs.length > 0 && position[0] == null) {
+                            parseError();
+                            return;
+                        }
+                        submitAction = updateVolume(id);
+                        cmd({
+                            RestoreVolume: {
+                                id,
+                                position: position[0],
+                            }
+                        });
+                        break;
+                    }
+                default:
+                    parseError();
+            }
+        } else if (args[1] == "purge") {
+            let days: number | null = null;
+            if (args.length > 2) {
+                days = Number.parseInt(args[2]);
+                if (!isNumber(days)) {
+                    parseError();
+                    return;
+                }
+            }
+            cmd({
+                PurgeTrash: {
+                    days,
+                },
+            });
+        } else {
+            parseError();
+        }
+    } else if (root == "restore") {
+        if (args.length == 1) {
+            cmd("Archives");
+            return;
+        }
+        const archive = args[1];
+        const mode = capitalize(args[2] ?? "replace") as RestoreMode;
+        if (!restoreModes.includes(mode)) {
+            parseError();
+            return;
+        }
+        if (args.length > 3) {
+            if (args[3] != "confirm") {
+                parseError();
+                return;
+            }
+            cmd({
+                Restore: {
+                    archive,
+                    mode,
+                },
+            });
+        } else {
+            cmd({
+                PreviewRestore: {
+                    archive,
+                    mode,
+                },
+            });
+        }
+    } else if (root == "snapshots") {
+        cmd("Snapshots");
+    } else if (root == "snapshot") {
+        cmd("TakeSnapshot");
+    } else if (root == "revisions") {
+        if (!expectArgs(2)) {
+            return;
+        }
+        const section = Number.parseInt(args[1]);
+        if (!isNumber(section)) {
+            parseError();
+            return;
+        }
+        cmd({
+            Revisions: {
+                id: section,
+            },
+        });
+    } else if (root == "revision") {
+        if (!expectArgs(4)) {
+            return;
+        }
+        const section = Number.parseInt(args[2]);
+        const numbers = args.slice(3).map(n => Number.parseInt(n));
+        if (!isNumber(section) || !numbers.every(isNumber)) {
+            parseError();
+            return;
+        }
+        if (args[1] == "diff") {
+            if (!expectArgs(5)) {
+                return;
+            }
+            cmd({
+                RevisionDiff: {
+                    id: section,
+                    from: numbers[0],
+                    to: numbers[1],
+                },
+            });
+        } else if (args[1] == "restore") {
+            cmd({
+                RestoreRevision: {
+                    id: section,
+                    revision: numbers[0],
+                },
+            });
+        } else {
+            parseError();
+        }
+    } else if (root == "submissions") {
+        cmd("Submissions");
+    } else if (root == "submission") {
+        if (!expectArgs(2)) {
+            return;
+        }
+        if (args.length == 2) {
+            const submission = Number.parseInt(args[1]);
+            if (!isNumber(submission)) {
+                parseError();
+                return;
+            }
+            cmd({
+                GetSubmission: {
+                    id: submission,
+                },
+            });
+            return;
+        }
+        if (!expectArgs(3)) {
+            return;
+        }
+        const submission = Number.parseInt(args[2]);
+        if (!isNumber(submission)) {
+            parseError();
+            return;
+        }
+        if (args[1] == "approve") {
+            const position = parseEntryPosition(args.slice(3));
+            if (position[0] == null) {
+                parseError();
+                return;
+            }
+            cmd({
+                ApproveSubmission: {
+                    id: submission,
+                    position: position[0],
+                },
+            });
+        } else if (args[1] == "reject" || args[1] == "revise") {
+            cmd({
+                ReturnSubmission: {
+                    id: submission,
+                    status: args[1] == "reject" ? "Rejected" : "ChangesRequested",
+                    note: args.slice(3).join(" "),
+                },
+            });
+        } else {
+            parseError();
+        }
     } else if (root == "init") {
         if (!expectArgs(2)) {
             return;
@@ -1164,5 +1572,30 @@ function cmd(command: Cmd) {
                 }
             });
         }
+
+        const archiveUploadInput = document.getElementById("archive-upload") as HTMLInputElement | null;
+        if (archiveUploadInput) {
+            // Archives console handling.
+            const uploadButton = document.getElementById("upload-archive") as HTMLButtonElement;
+            const elArchiveFeedback = document.getElementById("archive-feedback") as HTMLDivElement;
+
+            uploadButton.addEventListener("click", () => {
+                const file = archiveUploadInput.files![0];
+                if (!file) {
+                    return;
+                }
+                fetch(
+                    `/archive/${file.name}`,
+                    {
+                        method: "POST",
+                        headers: { "Content-Type": "application/zip" },
+                        body: file,
+                    }
+                ).then(res => res.text().then(text => {
+                    elArchiveFeedback.innerHTML += text;
+                    archiveUploadInput.value = "";
+                }));
+            });
+        }
     }));
 }
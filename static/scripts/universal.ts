@@ -93,6 +93,10 @@ export function processUtcs() {
         } else {
             el.innerHTML = `${MONTHS[date.getMonth()]} ${date.getDate()}, ${date.getFullYear()}`;
         }
+        if (el.classList.contains("time")) {
+            const minutes = date.getMinutes().toString().padStart(2, "0");
+            el.innerHTML += ` at ${date.getHours()}:${minutes}`;
+        }
         if (el.classList.contains("cap")) {
             el.innerHTML = el.innerHTML.toUpperCase();
         }
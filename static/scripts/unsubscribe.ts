@@ -0,0 +1 @@
+import "./universal.js";